@@ -0,0 +1,282 @@
+use std::{collections::VecDeque, time::Duration};
+
+use anyhow::Result;
+
+use crate::{backend::Backend, layout::Layout};
+
+use super::{channel::{self, EventSender}, AnimationCommand, AnimationContext, AnimationEvent, AnimationMessage, AnimationRunConfig, AnimationState, KeyCode, KeyModifiers, MessageCodec};
+
+pub enum Command<Msg> {
+    None,
+    Quit,
+    Message(Msg),
+    After(Duration, Msg),
+    Perform(Box<dyn FnOnce() -> Msg + Send>),
+    Batch(Vec<Command<Msg>>)
+}
+
+impl<Msg> Command<Msg> {
+    pub fn perform(task: impl FnOnce() -> Msg + Send + 'static) -> Self {
+        Command::Perform(Box::new(task))
+    }
+}
+
+pub enum Subscription<Msg> {
+    Keys(fn(KeyCode, KeyModifiers) -> Option<Msg>),
+    Resize(fn(usize, usize) -> Msg),
+    Paste(fn(String) -> Msg),
+    Focus(fn(bool) -> Msg),
+    Frame(fn(f64) -> Msg)
+}
+
+pub type AppContext<A> = AnimationContext<<A as App>::State, <A as App>::Msg>;
+
+pub trait App {
+    type State: AnimationState;
+    type Msg: AnimationMessage;
+
+    fn init(&self) -> (Self::State, Command<Self::Msg>);
+    fn update(&self, state: &mut Self::State, message: Self::Msg) -> Command<Self::Msg>;
+    fn view(&self, state: &Self::State) -> Layout<AppContext<Self>>;
+
+    fn subscriptions(&self, _state: &Self::State) -> Vec<Subscription<Self::Msg>> {
+        vec![]
+    }
+
+    // Lets event logs record messages, see `AnimatedTextCanvas::set_message_codec`
+    fn message_codec(&self) -> Option<MessageCodec<Self::Msg>> {
+        None
+    }
+}
+
+fn event_messages<Msg: AnimationMessage>(event: AnimationEvent<Msg>, subscriptions: &[Subscription<Msg>]) -> Vec<Msg> {
+    match event {
+        AnimationEvent::Message(message) => vec![message],
+        AnimationEvent::KeyEvent(code, modifiers) => subscriptions.iter().filter_map(|s| match s {
+            Subscription::Keys(f) => f(code, modifiers),
+            _ => None
+        }).collect(),
+        AnimationEvent::Resize(columns, rows) => subscriptions.iter().filter_map(|s| match s {
+            Subscription::Resize(f) => Some(f(columns, rows)),
+            _ => None
+        }).collect(),
+        AnimationEvent::Paste(text) => subscriptions.iter().filter_map(|s| match s {
+            Subscription::Paste(f) => Some(f(text.clone())),
+            _ => None
+        }).collect(),
+        AnimationEvent::FocusGained | AnimationEvent::FocusLost => {
+            let focused = matches!(event, AnimationEvent::FocusGained);
+
+            subscriptions.iter().filter_map(|s| match s {
+                Subscription::Focus(f) => Some(f(focused)),
+                _ => None
+            }).collect()
+        }
+    }
+}
+
+fn execute<State: AnimationState, Msg: AnimationMessage>(command: Command<Msg>, context: &mut AnimationContext<State, Msg>, queue: &mut VecDeque<Msg>) {
+    match command {
+        Command::None => {}
+        Command::Quit => context.add_command(AnimationCommand::Quit),
+        Command::Message(message) => queue.push_back(message),
+        Command::After(delay, message) => {
+            context.schedule_after(delay, AnimationEvent::Message(message));
+        }
+        Command::Perform(task) => {
+            let sender = context.event_sender();
+            std::thread::spawn(move || { sender.send(task()); });
+        }
+        Command::Batch(commands) => {
+            for command in commands {
+                execute(command, context, queue);
+            }
+        }
+    }
+}
+
+pub(crate) fn update<A: App>(app: &A, context: &mut AppContext<A>) {
+    let subscriptions = app.subscriptions(&context.state);
+
+    let mut queue = VecDeque::new();
+
+    // Each event is handled before the next one is taken, so a key goes to whatever has focus
+    // after the events before it
+    while let Some(event) = context.next_event() {
+        queue.extend(event_messages(event, &subscriptions));
+        run_queue(app, context, &mut queue);
+    }
+
+    for subscription in &subscriptions {
+        if let Subscription::Frame(f) = subscription {
+            queue.push_back(f(context.delta_milis));
+        }
+    }
+
+    run_queue(app, context, &mut queue);
+}
+
+fn run_queue<A: App>(app: &A, context: &mut AppContext<A>, queue: &mut VecDeque<A::Msg>) {
+    while let Some(message) = queue.pop_front() {
+        let command = app.update(&mut context.state, message);
+        execute(command, context, queue);
+    }
+}
+
+pub(crate) fn initial_context<A: App>(app: &A, sender: EventSender<A::Msg>) -> AppContext<A> {
+    let (state, command) = app.init();
+
+    let mut context = AnimationContext::new(state, sender);
+    context.message_codec = app.message_codec();
+
+    // Messages produced while initializing are handled on the first frame
+    let mut queue = VecDeque::new();
+    execute(command, &mut context, &mut queue);
+    context.pending_events.extend(queue.into_iter().map(AnimationEvent::Message));
+
+    context
+}
+
+#[cfg(feature = "crossterm")]
+pub fn run_app<A: App>(app: &A, config: AnimationRunConfig) -> Result<()> {
+    run_app_with_backend(app, config, &mut crate::backend::crossterm::CrosstermBackend::new())
+}
+
+pub fn run_app_with_backend<A: App>(app: &A, config: AnimationRunConfig, backend: &mut impl Backend) -> Result<()> {
+    let (sender, receiver) = channel::event_channel();
+    let context = initial_context(app, sender);
+
+    super::run_loop(
+        context,
+        &receiver,
+        config,
+        backend,
+        |context| app.view(&context.state),
+        |context| update(app, context)
+    )
+}
+
+#[cfg(feature = "async")]
+pub async fn run_app_async<A: App>(app: &A, config: AnimationRunConfig) -> Result<()> {
+    let mut backend = crate::backend::crossterm::CrosstermBackend::new();
+
+    run_app_async_with_backend(app, config, &mut backend, super::async_loop::crossterm_events()).await
+}
+
+#[cfg(feature = "async")]
+pub async fn run_app_async_with_backend<A: App>(
+    app: &A,
+    config: AnimationRunConfig,
+    backend: &mut impl Backend,
+    input: impl futures::Stream<Item = Result<crate::backend::BackendEvent>> + Unpin
+) -> Result<()> {
+    let (sender, receiver) = channel::event_channel();
+    let context = initial_context(app, sender);
+
+    super::async_loop::run_loop_async(
+        context,
+        &receiver,
+        config,
+        backend,
+        input,
+        |context| app.view(&context.state),
+        |context| update(app, context)
+    ).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{animation::driver::TestDriver, layout::geometry::Size};
+
+    use super::*;
+
+    // Keeps every message it's updated with, in order, and answers some of them
+    struct Script {
+        init: fn() -> Command<String>,
+        reply: fn(&str) -> Command<String>
+    }
+
+    impl App for Script {
+        type State = Vec<String>;
+        type Msg = String;
+
+        fn init(&self) -> (Vec<String>, Command<String>) {
+            (vec![], (self.init)())
+        }
+
+        fn update(&self, state: &mut Vec<String>, message: String) -> Command<String> {
+            let command = (self.reply)(&message);
+            state.push(message);
+
+            command
+        }
+
+        fn view(&self, _state: &Vec<String>) -> Layout<AppContext<Self>> {
+            Layout::text("")
+        }
+    }
+
+    fn message(text: &str) -> Command<String> {
+        Command::Message(text.to_string())
+    }
+
+    #[test]
+    fn init_messages_are_handled_in_order_on_the_first_frame() {
+        let app = Script {
+            init: || Command::Batch(vec![message("a"), message("b")]),
+            reply: |message| if message == "a" { self::message("a2") } else { Command::None }
+        };
+        let mut driver = TestDriver::for_app(&app, Size::new(1, 1));
+
+        assert!(driver.context().state.is_empty());
+
+        driver.step(16.0);
+        assert_eq!(driver.context().state, ["a", "a2", "b"]);
+    }
+
+    #[test]
+    fn a_delayed_message_waits_for_the_clock() {
+        let app = Script {
+            init: || Command::After(Duration::from_millis(100), "late".to_string()),
+            reply: |_| Command::None
+        };
+        let mut driver = TestDriver::for_app(&app, Size::new(1, 1));
+
+        driver.step(60.0);
+        driver.step(39.0);
+        assert!(driver.context().state.is_empty());
+
+        // Due once the clock reaches 100, handled by the update that follows
+        driver.step(1.0);
+        assert!(driver.context().state.is_empty());
+        driver.step(16.0);
+        assert_eq!(driver.context().state, ["late"]);
+
+        driver.step_frames(20, 16.0);
+        assert_eq!(driver.context().state, ["late"]);
+    }
+
+    #[test]
+    fn batches_run_in_order() {
+        let app = Script {
+            init: || message("go"),
+            reply: |message| match message {
+                "go" => Command::Batch(vec![
+                    self::message("1"),
+                    Command::After(Duration::ZERO, "later".to_string()),
+                    Command::Batch(vec![self::message("2"), self::message("3")]),
+                    self::message("4")
+                ]),
+                "2" => self::message("2a"),
+                _ => Command::None
+            }
+        };
+        let mut driver = TestDriver::for_app(&app, Size::new(1, 1));
+
+        driver.step(16.0);
+        assert_eq!(driver.context().state, ["go", "1", "2", "3", "4", "2a"]);
+
+        driver.step(16.0);
+        assert_eq!(driver.context().state, ["go", "1", "2", "3", "4", "2a", "later"]);
+    }
+}
@@ -0,0 +1,167 @@
+use anyhow::Result;
+use crossterm::event::EventStream;
+use futures::{future, FutureExt, Stream, StreamExt};
+
+use crate::{backend::{crossterm::{backend_event, CrosstermBackend}, Backend, BackendEvent}, layout::Layout, recording::RecordingBackend};
+
+use super::{channel::EventReceiver, frame_loop::{Frame, FrameLoop}, runner::{self, Runner}, terminal_modes, TerminalGuard, AnimatedTextCanvas, AnimationContext, AnimationMessage, AnimationRunConfig, AnimationState};
+
+impl<State: AnimationState, Msg: AnimationMessage> AnimatedTextCanvas<State, Msg> {
+    // Runs the same loop as `run_with_state`, but waits on input, timers and messages without
+    // blocking the thread. The future isn't Send: await it from the task that owns the UI or
+    // spawn it on a `LocalSet`.
+    pub async fn run_async(&self, state: State, config: AnimationRunConfig) -> Result<()> {
+        self.run_async_with_backend(state, config, &mut CrosstermBackend::new(), crossterm_events()).await
+    }
+
+    // Draws on `backend` and takes input from `input` instead of the terminal. The loop ends
+    // when `input` does.
+    pub async fn run_async_with_backend(&self, state: State, config: AnimationRunConfig, backend: &mut impl Backend, input: impl Stream<Item = Result<BackendEvent>> + Unpin) -> Result<()> {
+        let context = self.context(state);
+
+        run_loop_async(context, &self.receiver, config, backend, input, self.layout, self.update).await
+    }
+}
+
+// The terminal's input, as read by crossterm
+pub(crate) fn crossterm_events() -> impl Stream<Item = Result<BackendEvent>> + Unpin {
+    EventStream::new().filter_map(|event| future::ready(match event {
+        Ok(event) => backend_event(event).map(Ok),
+        Err(error) => Some(Err(error.into()))
+    }))
+}
+
+pub(crate) async fn run_loop_async<'a, State: AnimationState, Msg: AnimationMessage>(
+    context: AnimationContext<State, Msg>,
+    receiver: &EventReceiver<Msg>,
+    config: AnimationRunConfig,
+    backend: &mut impl Backend,
+    mut input: impl Stream<Item = Result<BackendEvent>> + Unpin,
+    layout_provider: impl Fn(&AnimationContext<State, Msg>)->Layout<AnimationContext<State, Msg>> + 'a,
+    update: impl FnMut(&mut AnimationContext<State, Msg>) + 'a
+) -> Result<()> {
+    let mut frames = FrameLoop::new(&config, backend)?;
+
+    let mut runner = Runner::new(context, frames.initial_size(), Box::new(layout_provider), Box::new(update));
+
+    runner.render();
+
+    match &config.record_path {
+        Some(path) => {
+            let mut recording = RecordingBackend::create(backend, path)?;
+            run_on_backend_async(&mut runner, &mut frames, &mut input, receiver, &config, &mut recording).await
+        }
+        None => run_on_backend_async(&mut runner, &mut frames, &mut input, receiver, &config, backend).await
+    }
+}
+
+async fn run_on_backend_async<State: AnimationState, Msg: AnimationMessage>(runner: &mut Runner<'_, State, Msg>, frames: &mut FrameLoop, input: &mut (impl Stream<Item = Result<BackendEvent>> + Unpin), receiver: &EventReceiver<Msg>, config: &AnimationRunConfig, backend: &mut impl Backend) -> Result<()> {
+    let terminal = TerminalGuard::enter(backend, &terminal_modes(config))?;
+
+    let result = drive_async(runner, frames, input, receiver, config, &mut *terminal.backend).await;
+
+    result.and(terminal.leave())
+}
+
+// Input comes from the stream, the backend is only drawn on and asked for job control signals
+async fn drive_async<State: AnimationState, Msg: AnimationMessage>(runner: &mut Runner<'_, State, Msg>, frames: &mut FrameLoop, input: &mut (impl Stream<Item = Result<BackendEvent>> + Unpin), receiver: &EventReceiver<Msg>, config: &AnimationRunConfig, backend: &mut impl Backend) -> Result<()> {
+    'frames: loop {
+        if !runner.update() { break; }
+
+        let Some(frame) = frames.begin_frame(runner, receiver)? else { break };
+
+        frames.present(runner, backend)?;
+
+        let events = match frame {
+            Frame::Replayed(events) => {
+                // A replay can still be interrupted from the keyboard
+                if let Some(Some(Ok(BackendEvent::Key(code, modifiers)))) = input.next().now_or_never() {
+                    if runner::is_exit_key(&code, &modifiers) { break; }
+                }
+
+                tokio::task::yield_now().await;
+
+                events
+            }
+            Frame::Live => match backend.take_signal()? {
+                // The input stream only reads the terminal, job control signals come from the backend
+                Some(event) => vec![event],
+                None => tokio::select! {
+                    event = input.next() => match event {
+                        Some(event) => vec![event?],
+                        None => break
+                    },
+                    _ = receiver.message_sent() => vec![],
+                    _ = tokio::time::sleep(frames.wait_timeout(runner)) => vec![]
+                }
+            }
+        };
+
+        for event in events {
+            if !frames.handle_event(runner, event, backend, config)? { break 'frames; }
+        }
+
+        frames.end_frame(runner);
+    }
+
+    frames.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::stream;
+
+    use crate::{animation::{AnimationCommand, AnimationEvent, KeyCode, KeyModifiers}, backend::memory::MemoryBackend};
+
+    use super::*;
+
+    // Collects typed characters and messages, asks for a timer on the first frame and quits once it fires
+    fn timer_canvas() -> AnimatedTextCanvas<String, String> {
+        let mut canvas = AnimatedTextCanvas::new(|context: &AnimationContext<String, String>| Layout::text(&context.state));
+        canvas.set_update(|context| {
+            if context.frame_count == 0 {
+                context.schedule_after(Duration::from_millis(30), AnimationEvent::Message("!".to_string()));
+            }
+
+            for event in context.take_events() {
+                match event {
+                    AnimationEvent::KeyEvent(KeyCode::Char(c), _) => context.state.push(c),
+                    AnimationEvent::Message(message) if message == "!" => context.add_command(AnimationCommand::Quit),
+                    AnimationEvent::Message(message) => context.state.push_str(&message),
+                    _ => {}
+                }
+            }
+        });
+
+        canvas
+    }
+
+    #[tokio::test]
+    async fn runs_on_any_backend_until_a_timer_quits() {
+        let canvas = timer_canvas();
+        canvas.event_sender().send("sent ".to_string());
+
+        let mut backend = MemoryBackend::new(10, 1);
+        // Input that never ends, so only the timer can stop the loop
+        let input = stream::iter([Ok(BackendEvent::Key(KeyCode::Char('a'), KeyModifiers::NONE))]).chain(stream::pending());
+
+        let run = canvas.run_async_with_backend(String::new(), AnimationRunConfig::default(), &mut backend, input);
+        tokio::time::timeout(Duration::from_secs(5), run).await.expect("the timer never fired").unwrap();
+
+        assert_eq!(backend.frame().to_string().trim_end(), "sent a");
+        assert!(backend.modes().is_none());
+    }
+
+    #[tokio::test]
+    async fn the_loop_ends_with_its_input() {
+        let canvas = timer_canvas();
+        let mut backend = MemoryBackend::new(10, 1);
+
+        let input = stream::iter([Ok(BackendEvent::Key(KeyCode::Char('a'), KeyModifiers::NONE))]);
+        canvas.run_async_with_backend(String::new(), AnimationRunConfig::default(), &mut backend, input).await.unwrap();
+
+        assert!(backend.draw_count() > 0);
+    }
+}
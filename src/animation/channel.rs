@@ -0,0 +1,88 @@
+use std::sync::{mpsc, Arc};
+
+use crate::backend::wake::Wake;
+
+use super::AnimationMessage;
+
+#[derive(Clone)]
+pub struct EventSender<Msg: AnimationMessage> {
+    sender: mpsc::Sender<Msg>,
+    // Cuts the loop's wait for input short
+    wake: Arc<Wake>,
+    #[cfg(feature = "async")]
+    notify: Arc<tokio::sync::Notify>
+}
+
+impl<Msg: AnimationMessage> EventSender<Msg> {
+    // Returns false when the animation loop is no longer around to receive the message.
+    pub fn send(&self, message: Msg) -> bool {
+        let sent = self.sender.send(message).is_ok();
+
+        self.wake.signal();
+
+        #[cfg(feature = "async")]
+        self.notify.notify_one();
+
+        sent
+    }
+}
+
+pub(crate) struct EventReceiver<Msg: AnimationMessage> {
+    receiver: mpsc::Receiver<Msg>,
+    wake: Arc<Wake>,
+    #[cfg(feature = "async")]
+    notify: Arc<tokio::sync::Notify>
+}
+
+impl<Msg: AnimationMessage> EventReceiver<Msg> {
+    pub(crate) fn drain(&self) -> Vec<Msg> {
+        // Taken first, so a message sent while draining still wakes the next wait
+        self.wake.take();
+
+        self.receiver.try_iter().collect()
+    }
+
+    // Signalled while there may be messages that weren't drained
+    pub(crate) fn wake(&self) -> &Wake {
+        &self.wake
+    }
+
+    // Resolves once a message was sent, including one sent before this started waiting
+    #[cfg(feature = "async")]
+    pub(crate) async fn message_sent(&self) {
+        self.notify.notified().await
+    }
+}
+
+pub(crate) fn event_channel<Msg: AnimationMessage>() -> (EventSender<Msg>, EventReceiver<Msg>) {
+    let (sender, receiver) = mpsc::channel();
+    let wake = Arc::new(Wake::new());
+
+    #[cfg(feature = "async")]
+    {
+        let notify = Arc::new(tokio::sync::Notify::new());
+
+        (EventSender { sender, wake: wake.clone(), notify: notify.clone() }, EventReceiver { receiver, wake, notify })
+    }
+
+    #[cfg(not(feature = "async"))]
+    (EventSender { sender, wake: wake.clone() }, EventReceiver { receiver, wake })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sending_wakes_the_receiver_until_it_drains() {
+        let (sender, receiver) = event_channel();
+        assert!(!receiver.wake().is_signalled());
+
+        sender.send(1);
+        sender.send(2);
+        assert!(receiver.wake().is_signalled());
+
+        assert_eq!(receiver.drain(), vec![1, 2]);
+        assert!(!receiver.wake().is_signalled());
+    }
+}
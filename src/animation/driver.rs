@@ -0,0 +1,393 @@
+use anyhow::Result;
+
+use crate::{backend::{memory::MemoryBackend, BackendEvent}, canvas::TextCanvas, layout::geometry::Size, recording::events::EventLog};
+
+use super::{app::{self, App}, channel::{self, EventReceiver, EventSender}, frame_loop::{self, FrameLoop}, runner::Runner, AnimatedTextCanvas, AnimationContext, AnimationEvent, AnimationMessage, AnimationRunConfig, AnimationState, KeyCode, KeyModifiers};
+
+// Runs an animation without a terminal: the canvas lives in memory, events are injected
+// by hand and time only moves forward when a frame is stepped. Events go through the same
+// frame loop as a real run, so resizes only reach the canvas once they've settled.
+pub struct TestDriver<'a, State: AnimationState, Msg: AnimationMessage = ()> {
+    runner: Runner<'a, State, Msg>,
+    frames: FrameLoop,
+    // Stands in for the terminal, at the size it was last resized to
+    backend: MemoryBackend,
+    config: AnimationRunConfig,
+    sender: EventSender<Msg>,
+    receiver: Inbox<'a, Msg>,
+    finished: bool
+}
+
+enum Inbox<'a, Msg: AnimationMessage> {
+    // The canvas's own channel, so senders taken from it before the driver existed still reach it
+    Canvas(&'a EventReceiver<Msg>),
+    Own(EventReceiver<Msg>)
+}
+
+impl<Msg: AnimationMessage> Inbox<'_, Msg> {
+    fn receiver(&self) -> &EventReceiver<Msg> {
+        match self {
+            Inbox::Canvas(receiver) => receiver,
+            Inbox::Own(receiver) => receiver
+        }
+    }
+}
+
+impl<'a, State: AnimationState + 'a, Msg: AnimationMessage> TestDriver<'a, State, Msg> {
+    pub fn new(canvas: &'a AnimatedTextCanvas<State, Msg>, state: State, size: Size) -> Self {
+        let sender = canvas.event_sender();
+        let context = canvas.context(state);
+
+        let runner = Runner::new(context, &size, Box::new(canvas.layout), Box::new(canvas.update));
+
+        Self::start(runner, sender, Inbox::Canvas(&canvas.receiver))
+    }
+
+    pub fn for_app<A: App<State = State, Msg = Msg>>(app: &'a A, size: Size) -> Self {
+        let (sender, receiver) = channel::event_channel();
+        let context = app::initial_context(app, sender.clone());
+
+        let runner = Runner::new(
+            context,
+            &size,
+            Box::new(|context| app.view(&context.state)),
+            Box::new(|context| app::update(app, context))
+        );
+
+        Self::start(runner, sender, Inbox::Own(receiver))
+    }
+}
+
+impl<'a, State: AnimationState, Msg: AnimationMessage> TestDriver<'a, State, Msg> {
+    fn start(mut runner: Runner<'a, State, Msg>, sender: EventSender<Msg>, receiver: Inbox<'a, Msg>) -> Self {
+        runner.render();
+
+        let size = runner.canvas.size();
+        let backend = MemoryBackend::new(size.width, size.height);
+        let config = AnimationRunConfig::default();
+        let frames = FrameLoop::new(&config, &backend).expect("a frame loop without logs can't fail");
+
+        TestDriver { runner, frames, backend, config, sender, receiver, finished: false }
+    }
+
+    pub fn send(&mut self, event: AnimationEvent<Msg>) {
+        if self.finished { return; }
+
+        let event = match event {
+            AnimationEvent::Message(message) => return self.runner.push_event(AnimationEvent::Message(message)),
+            AnimationEvent::KeyEvent(code, modifiers) => BackendEvent::Key(code, modifiers),
+            AnimationEvent::Resize(columns, rows) => {
+                self.backend = MemoryBackend::new(columns, rows);
+                BackendEvent::Resize(columns, rows)
+            }
+            AnimationEvent::Paste(text) => BackendEvent::Paste(text),
+            AnimationEvent::FocusGained => BackendEvent::FocusGained,
+            AnimationEvent::FocusLost => BackendEvent::FocusLost
+        };
+
+        // Without an event log or a terminal to suspend, handling an event can't fail
+        let running = self.frames.handle_event(&mut self.runner, event, &mut self.backend, &self.config).unwrap_or(false);
+        if !running { self.finished = true; }
+    }
+
+    pub fn key(&mut self, code: KeyCode) {
+        self.send(AnimationEvent::KeyEvent(code, KeyModifiers::NONE));
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.key(KeyCode::Char(c));
+        }
+    }
+
+    pub fn resize(&mut self, columns: usize, rows: usize) {
+        self.send(AnimationEvent::Resize(columns, rows));
+    }
+
+    // Runs one iteration of the loop as if `delta_milis` had passed since the last one.
+    // Returns false once the animation has quit.
+    pub fn step(&mut self, delta_milis: f64) -> bool {
+        let messages = self.receiver.receiver().drain();
+
+        self.frame(delta_milis, messages, vec![])
+    }
+
+    // Plays back a log written by `AnimationRunConfig::log_events_to` one frame at a time, the
+    // way `replay_events_from` does. Returns false once the animation has quit.
+    pub fn replay(&mut self, log: &EventLog) -> Result<bool> {
+        for frame in &log.frames {
+            let live = self.receiver.receiver().drain();
+            let messages = frame_loop::replayed_messages(&frame.messages, live, self.runner.context.message_codec.as_ref())?;
+
+            if !self.frame(frame.delta_milis, messages, frame.events.clone()) { return Ok(false); }
+        }
+
+        Ok(true)
+    }
+
+    // Events come in after the clock moved, before the render, like they do in the loop
+    fn frame(&mut self, delta_milis: f64, messages: Vec<Msg>, events: Vec<BackendEvent>) -> bool {
+        if self.finished { return false; }
+
+        if !self.runner.update() {
+            self.finished = true;
+            return false;
+        }
+
+        self.runner.advance(delta_milis, messages);
+
+        for event in events.into_iter().filter_map(frame_loop::animation_event) {
+            self.send(event);
+        }
+        if self.finished { return false; }
+
+        self.frames.end_frame(&mut self.runner);
+
+        true
+    }
+
+    pub fn step_frames(&mut self, count: usize, delta_milis: f64) -> bool {
+        for _ in 0..count {
+            if !self.step(delta_milis) { return false; }
+        }
+
+        true
+    }
+
+    pub fn canvas(&self) -> &TextCanvas {
+        &self.runner.canvas
+    }
+
+    pub fn context(&self) -> &AnimationContext<State, Msg> {
+        &self.runner.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut AnimationContext<State, Msg> {
+        &mut self.runner.context
+    }
+
+    pub fn event_sender(&self) -> EventSender<Msg> {
+        self.sender.clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{animation::app::Command, layout::Layout};
+
+    use super::*;
+
+    // Shows the state, which collects typed characters and messages
+    fn typing_canvas() -> AnimatedTextCanvas<String, String> {
+        let mut canvas = AnimatedTextCanvas::new(|context: &AnimationContext<String, String>| Layout::text(&context.state));
+        canvas.set_update(|context| {
+            for event in context.take_events() {
+                match event {
+                    AnimationEvent::KeyEvent(KeyCode::Char(c), _) => context.state.push(c),
+                    AnimationEvent::Message(text) => context.state.push_str(&text),
+                    AnimationEvent::Resize(columns, rows) => context.state = format!("{columns}x{rows}"),
+                    _ => {}
+                }
+            }
+        });
+
+        canvas
+    }
+
+    fn first_line(driver: &TestDriver<String, String>) -> String {
+        driver.canvas().to_string().lines().next().unwrap_or("").trim_end().to_string()
+    }
+
+    #[test]
+    fn typed_keys_show_up_after_a_step() {
+        let canvas = typing_canvas();
+        let mut driver = TestDriver::new(&canvas, String::new(), Size::new(10, 1));
+
+        driver.type_text("hi");
+        driver.step(16.0);
+
+        assert_eq!(first_line(&driver), "hi");
+    }
+
+    #[test]
+    fn senders_taken_from_the_canvas_beforehand_still_reach_the_driver() {
+        let canvas = typing_canvas();
+        let sender = canvas.event_sender();
+
+        let mut driver = TestDriver::new(&canvas, String::new(), Size::new(10, 1));
+
+        std::thread::spawn(move || sender.send("bg".to_string())).join().unwrap();
+
+        driver.step(16.0);
+        driver.step(16.0);
+
+        assert_eq!(first_line(&driver), "bg");
+    }
+
+    #[test]
+    fn timers_fire_as_time_is_stepped() {
+        let canvas = typing_canvas();
+        let mut driver = TestDriver::new(&canvas, String::new(), Size::new(10, 1));
+
+        driver.context_mut().schedule_after(Duration::from_millis(50), AnimationEvent::Message("!".to_string()));
+
+        driver.step_frames(3, 16.0);
+        assert_eq!(driver.context().state, "");
+
+        driver.step_frames(2, 16.0);
+        assert_eq!(driver.context().state, "!");
+    }
+
+    #[test]
+    fn the_canvas_follows_a_resize_once_it_settles() {
+        let canvas = typing_canvas();
+        let mut driver = TestDriver::new(&canvas, String::new(), Size::new(10, 1));
+
+        driver.resize(6, 2);
+        driver.step(16.0);
+        driver.resize(8, 3);
+
+        // Layouts see the new size right away, the canvas waits for the burst to end
+        driver.step_frames(3, 16.0);
+        assert_eq!(driver.context().viewport, Size::new(8, 3));
+        assert_eq!(driver.canvas().size(), &Size::new(10, 1));
+
+        driver.step_frames(2, 16.0);
+        assert_eq!(driver.canvas().size(), &Size::new(8, 3));
+        assert_eq!(first_line(&driver), "8x3");
+    }
+
+    #[test]
+    fn the_exit_key_finishes_the_run() {
+        let canvas = typing_canvas();
+        let mut driver = TestDriver::new(&canvas, String::new(), Size::new(10, 1));
+
+        driver.key(KeyCode::Esc);
+
+        assert!(driver.is_finished());
+        assert!(!driver.step(16.0));
+    }
+
+    // Two focusable fields that take every character key, as "<id>:<char> ". The first one
+    // also takes Left and Right, like a text field moving its cursor would.
+    fn focus_canvas() -> AnimatedTextCanvas<String, String> {
+        fn field(id: &str, handler: fn(&mut AnimationContext<String, String>, KeyCode, KeyModifiers) -> bool) -> Layout<AnimationContext<String, String>> {
+            Layout::text(id).focusable(id).on_key(handler)
+        }
+
+        fn typed(context: &mut AnimationContext<String, String>, id: &str, code: KeyCode) -> bool {
+            let KeyCode::Char(c) = code else { return false };
+            context.state.push_str(&format!("{id}:{c} "));
+
+            true
+        }
+
+        let mut canvas = AnimatedTextCanvas::new(|_: &AnimationContext<String, String>| Layout::horizontal_stack(vec![
+            field("a", |context, code, _| match code {
+                KeyCode::Left | KeyCode::Right => {
+                    context.state.push_str(&format!("a:{code:?} "));
+                    true
+                }
+                _ => typed(context, "a", code)
+            }),
+            field("b", |context, code, _| typed(context, "b", code))
+        ]));
+
+        canvas.set_update(|context| {
+            while let Some(event) = context.next_event() {
+                match event {
+                    AnimationEvent::Message(id) => context.focus.focus(&id),
+                    AnimationEvent::KeyEvent(code, _) => context.state.push_str(&format!("update:{code:?} ")),
+                    _ => {}
+                }
+            }
+        });
+
+        canvas
+    }
+
+    #[test]
+    fn arrows_move_focus_only_when_the_focused_element_declines_them() {
+        let canvas = focus_canvas();
+        let mut driver = TestDriver::new(&canvas, String::new(), Size::new(10, 1));
+
+        driver.key(KeyCode::Tab);
+        driver.key(KeyCode::Right);
+        driver.key(KeyCode::Down);
+        driver.type_text("x");
+        driver.key(KeyCode::Right);
+        driver.type_text("y");
+        driver.key(KeyCode::Up);
+        driver.key(KeyCode::Left);
+        driver.key(KeyCode::Right);
+        driver.key(KeyCode::Char('c'));
+
+        driver.key(KeyCode::Enter);
+        driver.step(16.0);
+
+        assert_eq!(driver.context().state, "a:Right b:x a:y a:Right a:c update:Enter ");
+        assert_eq!(driver.context().focus.focused(), Some("a"));
+    }
+
+    #[test]
+    fn keys_are_routed_after_the_events_queued_before_them() {
+        let canvas = focus_canvas();
+        let sender = canvas.event_sender();
+        let mut driver = TestDriver::new(&canvas, String::new(), Size::new(10, 1));
+
+        driver.key(KeyCode::Tab);
+        driver.step(16.0);
+
+        // The message focusing "b" is queued ahead of the key, the update hasn't seen it yet
+        sender.send("b".to_string());
+        driver.step(16.0);
+        driver.type_text("x");
+        driver.step(16.0);
+
+        assert_eq!(driver.context().state, "b:x ");
+    }
+
+    struct Counter;
+
+    impl App for Counter {
+        type State = usize;
+        type Msg = ();
+
+        fn init(&self) -> (usize, Command<()>) {
+            (0, Command::None)
+        }
+
+        fn update(&self, state: &mut usize, _message: ()) -> Command<()> {
+            *state += 1;
+
+            if *state == 2 { Command::Quit } else { Command::None }
+        }
+
+        fn view(&self, state: &usize) -> Layout<app::AppContext<Self>> {
+            Layout::text(&state.to_string())
+        }
+    }
+
+    #[test]
+    fn apps_run_until_they_quit() {
+        let mut driver = TestDriver::for_app(&Counter, Size::new(3, 1));
+        let sender = driver.event_sender();
+
+        sender.send(());
+        driver.step(16.0);
+        driver.step(16.0);
+        assert_eq!(driver.context().state, 1);
+        assert!(driver.canvas().to_string().starts_with('1'));
+
+        sender.send(());
+        driver.step(16.0);
+        assert!(!driver.step(16.0));
+        assert!(driver.is_finished());
+    }
+}
@@ -0,0 +1,62 @@
+// The focusable elements of the last rendered frame, in layout order, and which one has focus.
+#[derive(Clone, Debug, Default)]
+pub struct FocusRing {
+    order: Vec<String>,
+    focused: Option<String>
+}
+
+impl FocusRing {
+    pub fn focused(&self) -> Option<&str> {
+        self.focused.as_deref()
+    }
+
+    pub fn is_focused(&self, id: &str) -> bool {
+        self.focused() == Some(id)
+    }
+
+    pub fn ids(&self) -> &[String] {
+        &self.order
+    }
+
+    // The element doesn't need to be on screen yet, it only has to be there after the next render
+    pub fn focus(&mut self, id: &str) {
+        self.focused = Some(id.to_string());
+    }
+
+    pub fn blur(&mut self) {
+        self.focused = None;
+    }
+
+    pub fn focus_next(&mut self) {
+        let next = match self.position() {
+            Some(i) => (i + 1) % self.order.len(),
+            None => 0
+        };
+
+        self.focused = self.order.get(next).cloned();
+    }
+
+    pub fn focus_previous(&mut self) {
+        let previous = match self.position() {
+            Some(i) => (i + self.order.len() - 1) % self.order.len(),
+            None => self.order.len().saturating_sub(1)
+        };
+
+        self.focused = self.order.get(previous).cloned();
+    }
+
+    fn position(&self) -> Option<usize> {
+        let focused = self.focused.as_ref()?;
+
+        self.order.iter().position(|id| id == focused)
+    }
+
+    pub(crate) fn set_order(&mut self, order: Vec<String>) {
+        self.order = order;
+
+        // Focus doesn't stay on an element that's gone
+        if self.focused.is_some() && self.position().is_none() {
+            self.focused = None;
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use std::{fs::File, io::BufWriter, time::{Duration, Instant}};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::{backend::{Backend, BackendEvent}, layout::geometry::Size, recording::events::{EventLog, EventLogWriter, LoggedFrame}};
+
+use super::{channel::EventReceiver, runner::{self, Runner}, viewport_size, AnimationEvent, AnimationMessage, AnimationRunConfig, AnimationState, MessageCodec};
+
+// Without input, timers or messages coming in, frames are drawn this often
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+// Resizing a window by dragging sends a burst of events. The canvas only follows once they settle.
+const RESIZE_DEBOUNCE_MILIS: f64 = 50.0;
+
+enum InputSource {
+    // When the current frame started
+    Live(Instant),
+    Replay(std::vec::IntoIter<LoggedFrame>)
+}
+
+pub(crate) enum Frame {
+    Live,
+    // The input logged for this frame
+    Replayed(Vec<BackendEvent>)
+}
+
+// What a frame does between the update and the render, whichever loop is waiting on input
+pub(crate) struct FrameLoop {
+    input: InputSource,
+    // What the canvas starts at: the size a replayed log was written at, or the terminal's
+    initial_size: Size,
+    event_log: Option<EventLogWriter<BufWriter<File>>>,
+    // The latest size the terminal reported and when, while it's still changing
+    pending_resize: Option<(Size, f64)>
+}
+
+impl FrameLoop {
+    pub(crate) fn new(config: &AnimationRunConfig, backend: &impl Backend) -> Result<Self> {
+        let (input, logged_size) = match &config.replay_path {
+            Some(path) => {
+                let log = EventLog::load(path)?;
+                (InputSource::Replay(log.frames.into_iter()), log.size)
+            }
+            None => (InputSource::Live(Instant::now()), None)
+        };
+
+        let initial_size = logged_size.unwrap_or_else(|| viewport_size(backend, config));
+
+        let event_log = match &config.event_log_path {
+            Some(path) => {
+                let file = File::create(path)
+                    .with_context(|| format!("Couldn't create event log {}", path.display()))?;
+
+                Some(EventLogWriter::new(BufWriter::new(file), &initial_size)?)
+            }
+            None => None
+        };
+
+        Ok(FrameLoop { input, initial_size, event_log, pending_resize: None })
+    }
+
+    pub(crate) fn initial_size(&self) -> &Size {
+        &self.initial_size
+    }
+
+    // Moves the clock forward, from the wall clock or the replayed log. None once the replay ran out.
+    pub(crate) fn begin_frame<State: AnimationState, Msg: AnimationMessage>(&mut self, runner: &mut Runner<State, Msg>, receiver: &EventReceiver<Msg>) -> Result<Option<Frame>> {
+        let live_messages = receiver.drain();
+        let codec = runner.context.message_codec.clone();
+
+        let (delta_milis, frame, messages) = match &mut self.input {
+            InputSource::Live(last_time) => {
+                let delta_milis = last_time.elapsed().as_secs_f64().clamp(0.000001, f64::MAX) * 1000.0;
+                *last_time = Instant::now();
+
+                (delta_milis, Frame::Live, live_messages)
+            }
+            InputSource::Replay(frames) => match frames.next() {
+                Some(frame) => {
+                    let messages = replayed_messages(&frame.messages, live_messages, codec.as_ref())?;
+
+                    (frame.delta_milis, Frame::Replayed(frame.events), messages)
+                }
+                None => return Ok(None)
+            }
+        };
+
+        if let Some(log) = &mut self.event_log {
+            log.write_frame(delta_milis)?;
+
+            if let Some(codec) = &codec {
+                for message in &messages {
+                    log.write_message(&(codec.encode)(message))?;
+                }
+            }
+        }
+
+        runner.advance(delta_milis, messages);
+
+        Ok(Some(frame))
+    }
+
+    pub(crate) fn present<State: AnimationState, Msg: AnimationMessage>(&self, runner: &Runner<State, Msg>, backend: &mut impl Backend) -> Result<()> {
+        // The canvas no longer matches the terminal, drawing it would only get wrapped around
+        if self.pending_resize.is_some() { return Ok(()); }
+
+        backend.draw(&runner.canvas)?;
+        backend.flush()
+    }
+
+    // How long to wait on input: until the next timer is due or it's time for the next frame
+    pub(crate) fn wait_timeout<State: AnimationState, Msg: AnimationMessage>(&self, runner: &Runner<State, Msg>) -> Duration {
+        let until_next_frame = match &self.input {
+            InputSource::Live(frame_start) => FRAME_INTERVAL.saturating_sub(frame_start.elapsed()),
+            InputSource::Replay(_) => Duration::ZERO
+        };
+
+        runner.context.timers.next_due_in(runner.context.elapsed_milis)
+            .map_or(until_next_frame, |due| due.min(until_next_frame))
+    }
+
+    // Returns false when the event ends the loop
+    pub(crate) fn handle_event<State: AnimationState, Msg: AnimationMessage>(&mut self, runner: &mut Runner<State, Msg>, event: BackendEvent, backend: &mut impl Backend, config: &AnimationRunConfig) -> Result<bool> {
+        let suspend = match &event {
+            BackendEvent::Key(code, modifiers) if runner::is_exit_key(code, modifiers) => return Ok(false),
+            BackendEvent::Key(code, modifiers) => backend.can_suspend() && runner::is_suspend_key(code, modifiers),
+            BackendEvent::Suspend => true,
+            BackendEvent::Resume => {
+                self.resumed(runner, backend, config);
+                return Ok(true);
+            }
+            _ => false
+        };
+
+        if suspend {
+            backend.suspend()?;
+            self.resumed(runner, backend, config);
+
+            return Ok(true);
+        }
+
+        if let Some(log) = &mut self.event_log {
+            log.write_event(&event)?;
+        }
+
+        match event {
+            BackendEvent::Resize(columns, rows) => {
+                // Layouts can size themselves to the terminal right away, the canvas waits for it to settle
+                runner.context.viewport = Size::new(columns, rows);
+                self.pending_resize = Some((Size::new(columns, rows), runner.context.elapsed_milis));
+            }
+            event => if let Some(event) = animation_event(event) {
+                runner.push_event(event);
+            }
+        }
+
+        Ok(true)
+    }
+
+    // The terminal may have changed while we were away. The backend redraws everything on the
+    // next frame, so that repaints whatever the shell left on screen.
+    fn resumed<State: AnimationState, Msg: AnimationMessage>(&mut self, runner: &mut Runner<State, Msg>, backend: &impl Backend, config: &AnimationRunConfig) {
+        let size = viewport_size(backend, config);
+        if &size != runner.canvas.size() {
+            runner.push_event(AnimationEvent::Resize(size.width, size.height));
+        }
+
+        self.pending_resize = None;
+    }
+
+    pub(crate) fn end_frame<State: AnimationState, Msg: AnimationMessage>(&mut self, runner: &mut Runner<State, Msg>) {
+        if let Some((size, resized_at)) = &self.pending_resize {
+            if runner.context.elapsed_milis - resized_at >= RESIZE_DEBOUNCE_MILIS {
+                if size != runner.canvas.size() {
+                    runner.push_event(AnimationEvent::Resize(size.width, size.height));
+                }
+
+                self.pending_resize = None;
+            }
+        }
+
+        runner.render();
+        runner.context.frame_count += 1;
+    }
+
+    pub(crate) fn finish(&mut self) -> Result<()> {
+        if let Some(log) = &mut self.event_log {
+            log.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+// Job control stays between the loop and the backend, the animation never sees it
+pub(crate) fn animation_event<Msg>(event: BackendEvent) -> Option<AnimationEvent<Msg>> {
+    let event = match event {
+        BackendEvent::Key(code, modifiers) => AnimationEvent::KeyEvent(code, modifiers),
+        BackendEvent::Resize(columns, rows) => AnimationEvent::Resize(columns, rows),
+        BackendEvent::Paste(text) => AnimationEvent::Paste(text),
+        BackendEvent::FocusGained => AnimationEvent::FocusGained,
+        BackendEvent::FocusLost => AnimationEvent::FocusLost,
+        BackendEvent::Suspend | BackendEvent::Resume => return None
+    };
+
+    Some(event)
+}
+
+// The messages a replayed frame starts with. With a codec they come from the log and the live
+// ones are dropped, they'd only arrive whenever background work happened to finish.
+pub(crate) fn replayed_messages<Msg>(logged: &[String], live: Vec<Msg>, codec: Option<&MessageCodec<Msg>>) -> Result<Vec<Msg>> {
+    let Some(codec) = codec else { return Ok(live) };
+
+    logged.iter()
+        .map(|message| (codec.decode)(message).ok_or_else(|| anyhow!("Couldn't decode logged message '{message}'")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{animation::{channel, AnimationContext, KeyCode, KeyModifiers}, backend::{memory::MemoryBackend, TerminalModes}, canvas::TextCanvas, layout::Layout};
+
+    use super::*;
+
+    fn runner<'a>() -> Runner<'a, (), ()> {
+        let (sender, _) = channel::event_channel();
+
+        Runner::new(AnimationContext::new((), sender), &Size::new(1, 1), Box::new(|_| Layout::text("")), Box::new(|_| {}))
+    }
+
+    #[test]
+    fn waits_until_the_next_frame_without_timers() {
+        let frames = FrameLoop::new(&AnimationRunConfig::default(), &MemoryBackend::new(1, 1)).unwrap();
+
+        let timeout = frames.wait_timeout(&runner());
+        assert!(timeout > Duration::ZERO && timeout <= FRAME_INTERVAL);
+    }
+
+    #[test]
+    fn a_timer_due_before_the_next_frame_shortens_the_wait() {
+        let frames = FrameLoop::new(&AnimationRunConfig::default(), &MemoryBackend::new(1, 1)).unwrap();
+        let mut runner = runner();
+        runner.context.schedule_after(Duration::from_millis(3), AnimationEvent::Message(()));
+
+        assert!(frames.wait_timeout(&runner) <= Duration::from_millis(3));
+    }
+
+    #[test]
+    fn a_timer_due_after_the_next_frame_doesnt_stretch_the_wait() {
+        let frames = FrameLoop::new(&AnimationRunConfig::default(), &MemoryBackend::new(1, 1)).unwrap();
+        let mut runner = runner();
+        runner.context.schedule_after(Duration::from_secs(5), AnimationEvent::Message(()));
+
+        assert!(frames.wait_timeout(&runner) <= FRAME_INTERVAL);
+    }
+
+    #[test]
+    fn the_animation_only_sees_a_resume_as_the_size_it_came_back_to() {
+        let config = AnimationRunConfig::default();
+        let mut frames = FrameLoop::new(&config, &MemoryBackend::new(1, 1)).unwrap();
+        let mut runner = runner();
+        let mut backend = MemoryBackend::new(4, 2);
+
+        let mut resizes_after = |event, runner: &mut Runner<(), ()>, backend: &mut MemoryBackend| {
+            assert!(frames.handle_event(runner, event, backend, &config).unwrap());
+
+            runner.context.take_events().into_iter()
+                .map(|e| match e { AnimationEvent::Resize(columns, rows) => (columns, rows), _ => panic!("not a resize") })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(resizes_after(BackendEvent::Suspend, &mut runner, &mut backend), vec![(4, 2)]);
+        assert_eq!(resizes_after(BackendEvent::Resume, &mut runner, &mut backend), vec![]);
+
+        // Stopped from outside while the terminal shrank
+        backend.push_event(BackendEvent::Resize(3, 1));
+        backend.poll_event(Duration::ZERO).unwrap();
+        assert_eq!(resizes_after(BackendEvent::Resume, &mut runner, &mut backend), vec![(3, 1)]);
+
+        frames.handle_event(&mut runner, BackendEvent::Resize(9, 9), &mut backend, &config).unwrap();
+        frames.handle_event(&mut runner, BackendEvent::Resume, &mut backend, &config).unwrap();
+        assert!(frames.pending_resize.is_none());
+    }
+
+    // Counts the times it was asked to suspend
+    struct SuspendableBackend {
+        inner: MemoryBackend,
+        suspended: usize
+    }
+
+    impl Backend for SuspendableBackend {
+        fn size(&self) -> Result<Size> { self.inner.size() }
+        fn draw(&mut self, canvas: &TextCanvas) -> Result<()> { self.inner.draw(canvas) }
+        fn flush(&mut self) -> Result<()> { self.inner.flush() }
+        fn poll_event(&mut self, timeout: Duration) -> Result<Option<BackendEvent>> { self.inner.poll_event(timeout) }
+        fn enter(&mut self, modes: &TerminalModes) -> Result<()> { self.inner.enter(modes) }
+        fn leave(&mut self) -> Result<()> { self.inner.leave() }
+
+        fn suspend(&mut self) -> Result<()> {
+            self.suspended += 1;
+            Ok(())
+        }
+
+        fn can_suspend(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn ctrl_z_only_suspends_backends_that_can() {
+        let config = AnimationRunConfig::default();
+        let mut frames = FrameLoop::new(&config, &MemoryBackend::new(1, 1)).unwrap();
+        let mut runner = runner();
+        let ctrl_z = || BackendEvent::Key(KeyCode::Char('z'), KeyModifiers::CONTROL);
+
+        assert!(frames.handle_event(&mut runner, ctrl_z(), &mut MemoryBackend::new(1, 1), &config).unwrap());
+        assert!(matches!(
+            runner.context.take_events().as_slice(),
+            [AnimationEvent::KeyEvent(KeyCode::Char('z'), modifiers)] if *modifiers == KeyModifiers::CONTROL
+        ));
+
+        let mut backend = SuspendableBackend { inner: MemoryBackend::new(1, 1), suspended: 0 };
+        assert!(frames.handle_event(&mut runner, ctrl_z(), &mut backend, &config).unwrap());
+        assert_eq!(backend.suspended, 1);
+        assert!(runner.context.take_events().iter().all(|e| matches!(e, AnimationEvent::Resize(..))));
+    }
+
+    #[test]
+    fn the_viewport_follows_a_resize_before_the_canvas_does() {
+        let config = AnimationRunConfig::default();
+        let mut frames = FrameLoop::new(&config, &MemoryBackend::new(1, 1)).unwrap();
+        let mut runner = runner();
+        let mut backend = MemoryBackend::new(1, 1);
+
+        frames.handle_event(&mut runner, BackendEvent::Resize(8, 3), &mut backend, &config).unwrap();
+        frames.end_frame(&mut runner);
+
+        assert_eq!(runner.context.viewport, Size::new(8, 3));
+        assert_eq!(runner.canvas.size(), &Size::new(1, 1));
+
+        runner.context.elapsed_milis += RESIZE_DEBOUNCE_MILIS;
+        frames.end_frame(&mut runner);
+
+        assert_eq!(runner.canvas.size(), &Size::new(8, 3));
+    }
+}
@@ -0,0 +1,480 @@
+use std::{collections::HashMap, fmt::Display};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::layout::{alignment::{Edge, HorizontalAlignment}, Layout};
+
+use super::{KeyCode, KeyModifiers};
+
+// Bindings in this mode apply whatever the current mode is
+pub const GLOBAL_MODE: &str = "global";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers;
+
+        // The case of a character already says whether shift was held, and terminals don't
+        // agree on also reporting the modifier
+        if let KeyCode::Char(_) = code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+
+        KeyChord { code, modifiers }
+    }
+
+    // Reads chords like `q`, `G`, `Enter`, `Ctrl-x`, `Alt-Shift-Left` or `F5`
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.is_empty() { bail!("Empty key chord"); }
+
+        // A trailing `-` is the minus key itself, as in `Ctrl--`
+        let (prefix, key) = match text.strip_suffix("--") {
+            Some(prefix) => (prefix, "-"),
+            None if text == "-" => ("", "-"),
+            None => match text.rsplit_once('-') {
+                Some((prefix, key)) => (prefix, key),
+                None => ("", text)
+            }
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in prefix.split('-').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                "super" | "cmd" => KeyModifiers::SUPER,
+                _ => bail!("Unknown modifier '{modifier}' in '{text}'")
+            };
+        }
+
+        let code = match key.to_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "space" => KeyCode::Char(' '),
+            name => {
+                let mut chars = key.chars();
+
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                        Some(n) => KeyCode::F(n),
+                        None => bail!("Unknown key '{key}' in '{text}'")
+                    }
+                }
+            }
+        };
+
+        Ok(KeyChord::new(code, modifiers))
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = [
+            (KeyModifiers::CONTROL, "Ctrl"),
+            (KeyModifiers::ALT, "Alt"),
+            (KeyModifiers::SHIFT, "Shift"),
+            (KeyModifiers::SUPER, "Super")
+        ];
+
+        for (modifier, name) in names {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}-")?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code:?}")
+        }
+    }
+}
+
+// Space separated chords, e.g. `g g` or `Ctrl-x Ctrl-s`
+pub fn parse_sequence(text: &str) -> Result<Vec<KeyChord>> {
+    let sequence = text.split_whitespace()
+        .map(KeyChord::parse)
+        .collect::<Result<Vec<_>>>()?;
+
+    if sequence.is_empty() { bail!("Empty key sequence"); }
+
+    Ok(sequence)
+}
+
+#[derive(Clone, Debug)]
+struct Binding {
+    sequence: Vec<KeyChord>,
+    action: String
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeymapResult {
+    Action(String),
+    // The key started or continued a sequence, more keys are needed
+    Pending,
+    Unbound
+}
+
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    layers: HashMap<String, Vec<Binding>>,
+    descriptions: HashMap<String, String>,
+    mode: String,
+    pending: Vec<KeyChord>
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        Keymap {
+            layers: HashMap::new(),
+            descriptions: HashMap::new(),
+            mode: GLOBAL_MODE.to_string(),
+            pending: vec![]
+        }
+    }
+
+    pub fn bind(&mut self, keys: &str, action: &str) -> Result<()> {
+        self.bind_in(GLOBAL_MODE, keys, action)
+    }
+
+    // Binding the same keys again in a mode replaces the previous action
+    pub fn bind_in(&mut self, mode: &str, keys: &str, action: &str) -> Result<()> {
+        let sequence = parse_sequence(keys)?;
+
+        let layer = self.layers.entry(mode.to_string()).or_default();
+        layer.retain(|binding| binding.sequence != sequence);
+        layer.push(Binding { sequence, action: action.to_string() });
+
+        Ok(())
+    }
+
+    // Shown next to the keys in the help overlay instead of the action name
+    pub fn describe(&mut self, action: &str, description: &str) {
+        self.descriptions.insert(action.to_string(), description.to_string());
+    }
+
+    pub fn mode(&self) -> &str {
+        &self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: &str) {
+        self.mode = mode.to_string();
+        self.pending.clear();
+    }
+
+    // The chords of a sequence typed so far
+    pub fn pending(&self) -> &[KeyChord] {
+        &self.pending
+    }
+
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+
+    // Feeds one key press. A complete binding wins over longer ones that start the same way.
+    pub fn handle(&mut self, code: KeyCode, modifiers: KeyModifiers) -> KeymapResult {
+        let chord = KeyChord::new(code, modifiers);
+
+        self.pending.push(chord);
+        let result = self.lookup();
+
+        match result {
+            KeymapResult::Pending => {}
+            KeymapResult::Action(_) => self.pending.clear(),
+            KeymapResult::Unbound => {
+                // A sequence that went nowhere doesn't swallow the key that broke it
+                let retry = self.pending.len() > 1;
+                self.pending.clear();
+
+                if retry { return self.handle(code, modifiers); }
+            }
+        }
+
+        result
+    }
+
+    fn lookup(&self) -> KeymapResult {
+        let mut is_prefix = false;
+
+        for binding in self.active_bindings() {
+            if binding.sequence == self.pending {
+                return KeymapResult::Action(binding.action.clone());
+            }
+
+            if binding.sequence.starts_with(&self.pending) {
+                is_prefix = true;
+            }
+        }
+
+        if is_prefix { KeymapResult::Pending } else { KeymapResult::Unbound }
+    }
+
+    // The current mode's bindings first, so they can shadow the global ones
+    fn active_bindings(&self) -> impl Iterator<Item = &Binding> {
+        let mode = if self.mode == GLOBAL_MODE { None } else { self.layers.get(&self.mode) };
+
+        mode.into_iter().flatten()
+            .chain(self.layers.get(GLOBAL_MODE).into_iter().flatten())
+    }
+}
+
+// Config
+impl Keymap {
+    // Reads bindings from a TOML-like config. Sections name modes and bindings before the first
+    // section are global:
+    //
+    //     q = "quit"
+    //     "Ctrl-x Ctrl-s" = "save"
+    //
+    //     [insert]
+    //     Esc = "normal-mode"
+    pub fn from_config(config: &str) -> Result<Self> {
+        let mut keymap = Keymap::new();
+        let mut mode = GLOBAL_MODE.to_string();
+
+        for (i, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+
+            let malformed = |reason: &str| anyhow!("Malformed keymap config on line {}: {reason}: '{line}'", i + 1);
+
+            if let Some(section) = line.strip_prefix('[') {
+                let section = section.strip_suffix(']').ok_or_else(|| malformed("unclosed section"))?;
+                mode = section.trim().to_string();
+                continue;
+            }
+
+            let (keys, rest) = read_config_string(line).ok_or_else(|| malformed("expected keys"))?;
+            let rest = rest.trim_start().strip_prefix('=').ok_or_else(|| malformed("expected '='"))?;
+            let (action, rest) = read_config_string(rest.trim_start()).ok_or_else(|| malformed("expected an action"))?;
+
+            let rest = rest.trim();
+            if !rest.is_empty() && !rest.starts_with('#') {
+                bail!(malformed("unexpected text after the action"));
+            }
+
+            keymap.bind_in(&mode, &keys, &action)
+                .map_err(|e| malformed(&e.to_string()))?;
+        }
+
+        Ok(keymap)
+    }
+}
+
+// A quoted string with `\"` and `\\` escapes, or a bare word up to whitespace or `=`.
+// Returns it along with what's left of the line.
+fn read_config_string(text: &str) -> Option<(String, &str)> {
+    let Some(quoted) = text.strip_prefix('"') else {
+        let end = text.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(text.len());
+        if end == 0 { return None; }
+
+        return Some((text[..end].to_string(), &text[end..]));
+    };
+
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &quoted[i + 1..])),
+            '\\' => value.push(chars.next()?.1),
+            c => value.push(c)
+        }
+    }
+
+    None
+}
+
+// Help overlay
+impl Keymap {
+    // Lists the bindings that can be typed in the current mode, mode-specific ones first
+    pub fn help<Ctx: Clone>(&self) -> Layout<Ctx> {
+        let rows: Vec<_> = self.reachable_bindings()
+            .map(|binding| {
+                let keys = binding.sequence.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ");
+                let description = self.descriptions.get(&binding.action).unwrap_or(&binding.action);
+
+                (keys, description.clone())
+            })
+            .collect();
+
+        let keys_width = rows.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+
+        let mut lines = vec![Layout::text(&format!("Keys ({})", self.mode)).padding_bottom(1)];
+        lines.extend(rows.iter().map(|(keys, description)| {
+            Layout::text(&format!("{keys:<keys_width$}  {description}"))
+        }));
+
+        Layout::VerticalStack(HorizontalAlignment::Left, 0, lines)
+            .padding_horizontal(1)
+            .border(1, '#', Edge::all())
+            .background(' ')
+    }
+
+    // Leaves out the bindings `handle` never gets to: the ones a shorter binding completes
+    // before, and the ones an earlier binding of the same keys shadows
+    fn reachable_bindings(&self) -> impl Iterator<Item = &Binding> {
+        let active: Vec<_> = self.active_bindings().collect();
+
+        active.clone().into_iter().enumerate()
+            .filter(move |(i, binding)| !active.iter().enumerate().any(|(j, other)| {
+                let shorter = other.sequence.len() < binding.sequence.len() && binding.sequence.starts_with(&other.sequence);
+
+                shorter || (j < *i && other.sequence == binding.sequence)
+            }))
+            .map(|(_, binding)| binding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord::new(code, modifiers)
+    }
+
+    #[test]
+    fn chords_parse_with_their_modifiers() {
+        assert_eq!(KeyChord::parse("q").unwrap(), chord(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert_eq!(KeyChord::parse("Ctrl-x").unwrap(), chord(KeyCode::Char('x'), KeyModifiers::CONTROL));
+        assert_eq!(KeyChord::parse("alt-shift-Left").unwrap(), chord(KeyCode::Left, KeyModifiers::ALT | KeyModifiers::SHIFT));
+        assert_eq!(KeyChord::parse(" F5 ").unwrap(), chord(KeyCode::F(5), KeyModifiers::NONE));
+        assert_eq!(KeyChord::parse("Space").unwrap(), chord(KeyCode::Char(' '), KeyModifiers::NONE));
+        assert_eq!(KeyChord::parse("-").unwrap(), chord(KeyCode::Char('-'), KeyModifiers::NONE));
+        assert_eq!(KeyChord::parse("Ctrl--").unwrap(), chord(KeyCode::Char('-'), KeyModifiers::CONTROL));
+    }
+
+    #[test]
+    fn shift_is_dropped_from_characters() {
+        assert_eq!(KeyChord::parse("Shift-G").unwrap(), KeyChord::parse("G").unwrap());
+        assert_eq!(KeyChord::new(KeyCode::Char('G'), KeyModifiers::SHIFT).modifiers, KeyModifiers::NONE);
+    }
+
+    #[test]
+    fn unknown_keys_and_modifiers_are_errors() {
+        for text in ["", "Hyper-x", "c-x", "s-Left", "nope", "Ctrl-"] {
+            assert!(KeyChord::parse(text).is_err(), "{text:?} parsed");
+        }
+    }
+
+    #[test]
+    fn chords_print_back_the_way_they_parse() {
+        for text in ["Ctrl-x", "Alt-Shift-Left", "F5", "Space", "G"] {
+            assert_eq!(KeyChord::parse(text).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn sequences_wait_for_their_last_key() {
+        let mut keymap = Keymap::new();
+        keymap.bind("g g", "top").unwrap();
+        keymap.bind("q", "quit").unwrap();
+
+        assert_eq!(keymap.handle(KeyCode::Char('g'), KeyModifiers::NONE), KeymapResult::Pending);
+        assert_eq!(keymap.handle(KeyCode::Char('g'), KeyModifiers::NONE), KeymapResult::Action("top".to_string()));
+
+        // The key that broke the sequence still counts on its own
+        keymap.handle(KeyCode::Char('g'), KeyModifiers::NONE);
+        assert_eq!(keymap.handle(KeyCode::Char('q'), KeyModifiers::NONE), KeymapResult::Action("quit".to_string()));
+        assert!(keymap.pending().is_empty());
+    }
+
+    #[test]
+    fn mode_bindings_shadow_global_ones() {
+        let mut keymap = Keymap::new();
+        keymap.bind("q", "quit").unwrap();
+        keymap.bind_in("insert", "q", "type-q").unwrap();
+
+        assert_eq!(keymap.handle(KeyCode::Char('q'), KeyModifiers::NONE), KeymapResult::Action("quit".to_string()));
+
+        keymap.set_mode("insert");
+        assert_eq!(keymap.handle(KeyCode::Char('q'), KeyModifiers::NONE), KeymapResult::Action("type-q".to_string()));
+    }
+
+    #[test]
+    fn configs_bind_keys_per_mode() {
+        let config = r#"
+            # Comments and blank lines are skipped
+            q = "quit"
+            "Ctrl-x Ctrl-s" = save   # trailing comment
+
+            [insert]
+            Esc = "normal-mode"
+        "#;
+
+        let mut keymap = Keymap::from_config(config).unwrap();
+
+        assert_eq!(keymap.handle(KeyCode::Char('q'), KeyModifiers::NONE), KeymapResult::Action("quit".to_string()));
+        keymap.handle(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.handle(KeyCode::Char('s'), KeyModifiers::CONTROL), KeymapResult::Action("save".to_string()));
+        assert_eq!(keymap.handle(KeyCode::Esc, KeyModifiers::NONE), KeymapResult::Unbound);
+
+        keymap.set_mode("insert");
+        assert_eq!(keymap.handle(KeyCode::Esc, KeyModifiers::NONE), KeymapResult::Action("normal-mode".to_string()));
+    }
+
+    #[test]
+    fn malformed_configs_are_errors() {
+        for config in ["[insert", "q", "q =", "q = quit extra", "\"unclosed = quit", "Hyper-q = quit"] {
+            assert!(Keymap::from_config(config).is_err(), "{config:?} parsed");
+        }
+    }
+
+    fn help_lines(keymap: &Keymap) -> Vec<String> {
+        let mut canvas = crate::canvas::TextCanvas::create(40, 10);
+        canvas.render_layout(&keymap.help::<()>(), &mut ());
+
+        canvas.to_string().lines()
+            .map(|line| line.trim_matches(|c| c == ' ' || c == '#').to_string())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn the_help_leaves_out_shadowed_bindings() {
+        let mut keymap = Keymap::new();
+        keymap.bind("q", "quit").unwrap();
+        keymap.bind("g g", "top").unwrap();
+        keymap.bind("x", "delete").unwrap();
+        keymap.bind_in("insert", "q", "type-q").unwrap();
+        keymap.bind_in("insert", "g", "type-g").unwrap();
+        keymap.describe("delete", "Delete a character");
+        keymap.set_mode("insert");
+
+        assert_eq!(help_lines(&keymap), vec![
+            "Keys (insert)",
+            "q  type-q",
+            "g  type-g",
+            "x  Delete a character"
+        ]);
+    }
+}
@@ -1,19 +1,43 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
-use crossterm::event::KeyEvent;
 
-use crate::{canvas::TextCanvas, layout::{geometry::Size, Layout}};
-use defer_lite::defer;
+use crate::{backend::{Backend, BackendEvent, TerminalModes}, layout::{focus::KeyHandler, geometry::Size, Layout}, recording::RecordingBackend};
+
+pub mod timers;
+pub mod channel;
+pub mod app;
+pub mod tween;
+pub mod driver;
+pub mod focus;
+pub mod keymap;
+mod runner;
+mod frame_loop;
+#[cfg(feature = "async")]
+mod async_loop;
+
+use timers::{TimerHandle, Timers};
+use channel::{EventReceiver, EventSender};
+use focus::FocusRing;
+use runner::Runner;
+use frame_loop::{Frame, FrameLoop};
 
 pub trait AnimationState: Clone {}
 impl <T: Clone> AnimationState for T {}
 
-pub type KeyCode = crossterm::event::KeyCode;
-pub type KeyModifiers = crossterm::event::KeyModifiers;
+pub trait AnimationMessage: Clone + Send + 'static {}
+impl <T: Clone + Send + 'static> AnimationMessage for T {}
+
+pub use crate::backend::keys::{KeyCode, KeyModifiers};
 
 #[derive(Clone)]
-pub enum AnimationEvent {
+pub enum AnimationEvent<Msg = ()> {
     KeyEvent(KeyCode, KeyModifiers),
-    Resize(usize, usize)
+    Resize(usize, usize),
+    Paste(String),
+    FocusGained,
+    FocusLost,
+    Message(Msg)
 }
 
 #[derive(Clone)]
@@ -22,185 +46,579 @@ pub enum AnimationCommand {
 }
 
 #[derive(Clone)]
-pub struct AnimationContext<State: AnimationState> {
+pub struct AnimationContext<State: AnimationState, Msg: AnimationMessage = ()> {
     pub frame_count: usize,
     pub delta_milis: f64,
     pub state: State,
-    pub pending_events: Vec<AnimationEvent>,
-    pub commands: Vec<AnimationCommand>
+    pub pending_events: Vec<AnimationEvent<Msg>>,
+    pub commands: Vec<AnimationCommand>,
+    pub elapsed_milis: f64,
+    pub timers: Timers<Msg>,
+    pub viewport: Size,
+    pub focus: FocusRing,
+    // The key handlers of the focusable elements in the last rendered frame
+    key_handlers: HashMap<String, KeyHandler<AnimationContext<State, Msg>>>,
+    sender: EventSender<Msg>,
+    message_codec: Option<MessageCodec<Msg>>
 }
 
 pub type PlainAnimationContext = AnimationContext<()>;
 impl Default for PlainAnimationContext {
     fn default() -> Self {
+        // Nothing drains this channel, messages sent through it are dropped.
+        let (sender, _) = channel::event_channel();
+
+        AnimationContext::new((), sender)
+    }
+}
+
+impl<State: AnimationState, Msg: AnimationMessage> AnimationContext<State, Msg> {
+    fn new(state: State, sender: EventSender<Msg>) -> Self {
         AnimationContext {
             frame_count: 0,
             delta_milis: 0.0,
-            state: (),
+            state,
             pending_events: vec![],
-            commands: vec![]
+            commands: vec![],
+            elapsed_milis: 0.0,
+            timers: Timers::default(),
+            viewport: Size::zero(),
+            focus: FocusRing::default(),
+            key_handlers: HashMap::new(),
+            sender,
+            message_codec: None
         }
     }
-}
 
-impl<T: Clone> AnimationContext<T> {
+    pub fn event_sender(&self) -> EventSender<Msg> {
+        self.sender.clone()
+    }
+
     pub fn add_command(&mut self, command: AnimationCommand) {
         self.commands.push(command)
     }
+
+    // Removes the next pending event. Keys go to the focused element first, then Tab, BackTab
+    // and the arrow keys move focus, so the update only gets the keys nothing else handled.
+    pub fn next_event(&mut self) -> Option<AnimationEvent<Msg>> {
+        while !self.pending_events.is_empty() {
+            let event = self.pending_events.remove(0);
+
+            if let AnimationEvent::KeyEvent(code, modifiers) = event {
+                if self.route_key(code, modifiers) { continue; }
+            }
+
+            return Some(event);
+        }
+
+        None
+    }
+
+    // Removes the pending events in the order they arrived, routing keys like `next_event` does
+    pub fn take_events(&mut self) -> Vec<AnimationEvent<Msg>> {
+        std::iter::from_fn(|| self.next_event()).collect()
+    }
+
+    // Returns true when the update shouldn't see the key
+    fn route_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let handler = self.focus.focused()
+            .and_then(|id| self.key_handlers.get(id).copied());
+
+        if let Some(handler) = handler {
+            if handler(self, code, modifiers) { return true; }
+        }
+
+        if self.focus.ids().is_empty() { return false; }
+        if modifiers.contains(KeyModifiers::CONTROL) || modifiers.contains(KeyModifiers::ALT) { return false; }
+
+        match code {
+            KeyCode::Tab | KeyCode::Down | KeyCode::Right => self.focus.focus_next(),
+            KeyCode::BackTab | KeyCode::Up | KeyCode::Left => self.focus.focus_previous(),
+            _ => return false
+        }
+
+        true
+    }
+
+    pub fn schedule_after(&mut self, delay: std::time::Duration, event: AnimationEvent<Msg>) -> TimerHandle {
+        self.timers.schedule(self.elapsed_milis, delay, None, event)
+    }
+
+    pub fn every(&mut self, interval: std::time::Duration, event: AnimationEvent<Msg>) -> TimerHandle {
+        self.timers.schedule(self.elapsed_milis, interval, Some(interval), event)
+    }
+
+    pub fn cancel_timer(&mut self, handle: TimerHandle) -> bool {
+        self.timers.cancel(handle)
+    }
+
+    fn advance_clock(&mut self, delta_milis: f64) {
+        self.delta_milis = delta_milis;
+        self.elapsed_milis += delta_milis;
+
+        let due_events = self.timers.take_due(self.elapsed_milis);
+        self.pending_events.extend(due_events);
+    }
+
+    fn receive_messages(&mut self, messages: Vec<Msg>) {
+        self.pending_events.extend(messages.into_iter().map(AnimationEvent::Message));
+    }
 }
 
-#[derive(PartialEq)]
+// Turns messages into a line of text and back, so event logs can record the messages sent
+// through an `EventSender` and replays don't depend on background work finishing in time
+pub struct MessageCodec<Msg> {
+    pub encode: fn(&Msg) -> String,
+    pub decode: fn(&str) -> Option<Msg>
+}
+
+impl<Msg> Clone for MessageCodec<Msg> {
+    fn clone(&self) -> Self {
+        MessageCodec { encode: self.encode, decode: self.decode }
+    }
+}
+
+#[derive(PartialEq, Default)]
 pub enum AnimationBuffer {
+    #[default]
     Main,
     Alternate
 }
 
-impl Default for AnimationBuffer {
-    fn default() -> Self {
-        Self::Main
+#[derive(Default)]
+pub struct AnimationRunConfig {
+    pub buffer_type: AnimationBuffer,
+    pub record_path: Option<std::path::PathBuf>,
+    pub event_log_path: Option<std::path::PathBuf>,
+    pub replay_path: Option<std::path::PathBuf>,
+    pub bracketed_paste: bool,
+    pub focus_events: bool,
+    pub fallback_size: Option<Size>
+}
+
+impl AnimationRunConfig {
+    // Captures every rendered frame into an asciinema v2 cast at `path`
+    pub fn record_to(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.record_path = Some(path.into());
+        self
+    }
+
+    // Pasted text arrives as a single `AnimationEvent::Paste` instead of one key event per character
+    pub fn with_bracketed_paste(mut self) -> Self {
+        self.bracketed_paste = true;
+        self
+    }
+
+    // The animation gets `AnimationEvent::FocusGained` and `FocusLost` when the terminal window
+    // gains or loses focus, in terminals that report it
+    pub fn with_focus_events(mut self) -> Self {
+        self.focus_events = true;
+        self
+    }
+
+    // The size to render at when the backend can't tell, e.g. when stdout isn't a terminal.
+    // Defaults to 80x24.
+    pub fn with_fallback_size(mut self, width: usize, height: usize) -> Self {
+        self.fallback_size = Some(Size::new(width, height));
+        self
+    }
+
+    // Logs every input event with the frame it arrived on and the frame timings
+    pub fn log_events_to(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.event_log_path = Some(path.into());
+        self
+    }
+
+    // Takes input and frame timings from a log written by `log_events_to` instead of the
+    // backend and the clock. The run ends when the log does.
+    pub fn replay_events_from(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.replay_path = Some(path.into());
+        self
     }
 }
 
-#[derive(Default)]
-pub struct AnimationRunConfig {
-    pub buffer_type: AnimationBuffer
+const DEFAULT_VIEWPORT_SIZE: Size = Size { width: 80, height: 24 };
+
+fn viewport_size(backend: &impl Backend, config: &AnimationRunConfig) -> Size {
+    backend.size().ok()
+        .filter(|size| size.width > 0 && size.height > 0)
+        .or_else(|| config.fallback_size.clone())
+        .unwrap_or(DEFAULT_VIEWPORT_SIZE)
 }
 
-type AnimatedLayoutProvider<State> = fn(&AnimationContext<State>)->Layout<AnimationContext<State>>;
-pub struct AnimatedTextCanvas<State: AnimationState> {
-    layout: AnimatedLayoutProvider<State>,
-    update: fn(&mut AnimationContext<State>)
+type AnimatedLayoutProvider<State, Msg> = fn(&AnimationContext<State, Msg>)->Layout<AnimationContext<State, Msg>>;
+pub struct AnimatedTextCanvas<State: AnimationState, Msg: AnimationMessage = ()> {
+    layout: AnimatedLayoutProvider<State, Msg>,
+    update: fn(&mut AnimationContext<State, Msg>),
+    message_codec: Option<MessageCodec<Msg>>,
+    sender: EventSender<Msg>,
+    receiver: EventReceiver<Msg>
 }
 
-impl<State: AnimationState> AnimatedTextCanvas<State> {
-    fn clear_buffer(&self) {
-        crossterm::execute!(
-            std::io::stdout(),
-            crossterm::terminal::Clear(crossterm::terminal::ClearType::Purge),
-            crossterm::cursor::MoveTo(0, 0),
-        ).unwrap();
-    }
-    
-    pub fn set_update(&mut self, update_fn: fn(&mut AnimationContext<State>)) {
+impl<State: AnimationState, Msg: AnimationMessage> AnimatedTextCanvas<State, Msg> {
+    pub fn set_update(&mut self, update_fn: fn(&mut AnimationContext<State, Msg>)) {
         self.update = update_fn;
     }
+
+    pub fn event_sender(&self) -> EventSender<Msg> {
+        self.sender.clone()
+    }
+
+    // Without it, event logs leave messages out and replays take them from the channel as they come
+    pub fn set_message_codec(&mut self, encode: fn(&Msg) -> String, decode: fn(&str) -> Option<Msg>) {
+        self.message_codec = Some(MessageCodec { encode, decode });
+    }
+
+    fn context(&self, state: State) -> AnimationContext<State, Msg> {
+        let mut context = AnimationContext::new(state, self.sender.clone());
+        context.message_codec = self.message_codec.clone();
+
+        context
+    }
 }
 
-impl<State: AnimationState> AnimatedTextCanvas<State> {
-    pub fn new(layout: AnimatedLayoutProvider<State>) -> Self {
-        AnimatedTextCanvas { layout, update: |_|{} }
+type PanicHook = std::sync::Arc<Box<dyn Fn(&std::panic::PanicHookInfo<'_>) + Sync + Send + 'static>>;
+
+// The hook that was there before ours, and whether ours still has a terminal to restore
+struct InstalledPanicHook {
+    previous: PanicHook,
+    active: std::sync::Arc<std::sync::atomic::AtomicBool>
+}
+
+fn install_panic_hook(restore_terminal: Box<dyn Fn() + Send + Sync>) -> InstalledPanicHook {
+    let previous: PanicHook = std::sync::Arc::new(std::panic::take_hook());
+    let active = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+
+    let hook = previous.clone();
+    let is_active = active.clone();
+    // Panics on other threads, like the ones behind an `EventSender`, leave the loop drawing
+    let loop_thread = std::thread::current().id();
+
+    std::panic::set_hook(Box::new(move |info| {
+        // The terminal has to be usable again before the panic message and backtrace get
+        // printed, otherwise they end up scattered across the alternate screen in raw mode.
+        if is_active.load(std::sync::atomic::Ordering::SeqCst) && std::thread::current().id() == loop_thread {
+            restore_terminal();
+        }
+
+        hook(info);
+    }));
+
+    InstalledPanicHook { previous, active }
+}
+
+fn uninstall_panic_hook(installed: InstalledPanicHook) {
+    // Whether or not ours can be taken out, it stops touching the terminal
+    installed.active.store(false, std::sync::atomic::Ordering::SeqCst);
+
+    // `set_hook` can't be called while unwinding, ours stays in place and only forwards
+    if std::thread::panicking() { return; }
+
+    // Dropping ours leaves `previous` as the only owner of the original hook
+    drop(std::panic::take_hook());
+
+    match std::sync::Arc::try_unwrap(installed.previous) {
+        Ok(original) => std::panic::set_hook(original),
+        Err(previous) => std::panic::set_hook(Box::new(move |info| previous(info)))
     }
+}
 
-    pub fn run_with_state(&self, state: State, config: AnimationRunConfig) -> Result<()> {
-        let mut stdout = std::io::stdout();
+// Leaves the terminal and takes the panic hook back out however the loop ends, unwinding included
+struct TerminalGuard<'b, B: Backend> {
+    backend: &'b mut B,
+    panic_hook: Option<InstalledPanicHook>
+}
 
-        let (terminal_columns, terminal_rows ) = crossterm::terminal::size().unwrap();
+impl<'b, B: Backend> TerminalGuard<'b, B> {
+    fn enter(backend: &'b mut B, modes: &TerminalModes) -> Result<Self> {
+        backend.enter(modes)?;
 
-        let mut terminal_columns = terminal_columns as usize;
-        let mut terminal_rows = terminal_rows as usize;
+        let panic_hook = install_panic_hook(backend.panic_restore());
 
-        let bounds = &Size::new(terminal_columns, terminal_rows);
-        // let bounds = &Rect::sized(20, 5);
-        let mut canvas = TextCanvas::create_in_bounds(bounds);
+        Ok(TerminalGuard { backend, panic_hook: Some(panic_hook) })
+    }
 
-        let mut context = AnimationContext {
-            frame_count: 0,
-            delta_milis: 0.0,
-            state,
-            pending_events: vec![],
-            commands: vec![]
-        };
+    // What dropping does, but with the error from leaving
+    fn leave(mut self) -> Result<()> {
+        self.restore()
+    }
 
-        let layout = (self.layout)(&mut context);
+    fn restore(&mut self) -> Result<()> {
+        let Some(panic_hook) = self.panic_hook.take() else { return Ok(()) };
 
-        canvas.render_layout(&layout, &mut context);
+        let left = self.backend.leave();
+        uninstall_panic_hook(panic_hook);
 
-        crossterm::terminal::enable_raw_mode().unwrap_or_else(|_| {
-            crossterm::terminal::disable_raw_mode().unwrap();
-        });
-        defer! { let _ = crossterm::terminal::disable_raw_mode(); }
+        left
+    }
+}
 
-        let mut last_time = std::time::Instant::now();
+impl<B: Backend> Drop for TerminalGuard<'_, B> {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
 
-        if config.buffer_type == AnimationBuffer::Alternate {
-            crossterm::execute!(stdout, crossterm::terminal::EnterAlternateScreen)?;
-        }
+impl<State: AnimationState, Msg: AnimationMessage> AnimatedTextCanvas<State, Msg> {
+    pub fn new(layout: AnimatedLayoutProvider<State, Msg>) -> Self {
+        let (sender, receiver) = channel::event_channel();
 
-        defer!{
-            if config.buffer_type == AnimationBuffer::Alternate {
-                crossterm::execute!(std::io::stdout(), crossterm::terminal::LeaveAlternateScreen)
-                .unwrap_or_else(|err| { println!("Error exiting alternate screen buffer:\n{err}"); });
-            }
-        }
+        AnimatedTextCanvas { layout, update: |_|{}, message_codec: None, sender, receiver }
+    }
+
+    #[cfg(feature = "crossterm")]
+    pub fn run_with_state(&self, state: State, config: AnimationRunConfig) -> Result<()> {
+        self.run_with_backend(state, config, &mut crate::backend::crossterm::CrosstermBackend::new())
+    }
 
-        crossterm::execute!(stdout, crossterm::cursor::Hide)?;
-        
-        defer!{
-            crossterm::execute!(std::io::stdout(), crossterm::cursor::Show)
-                .unwrap_or_else(|err| { println!("Error restoring cursor state:\n{err}"); });
+    pub fn run_with_backend(&self, state: State, config: AnimationRunConfig, backend: &mut impl Backend) -> Result<()> {
+        let context = self.context(state);
+
+        run_loop(context, &self.receiver, config, backend, self.layout, self.update)
+    }
+}
+
+pub(crate) fn run_loop<'a, State: AnimationState, Msg: AnimationMessage>(
+    context: AnimationContext<State, Msg>,
+    receiver: &EventReceiver<Msg>,
+    config: AnimationRunConfig,
+    backend: &mut impl Backend,
+    layout_provider: impl Fn(&AnimationContext<State, Msg>)->Layout<AnimationContext<State, Msg>> + 'a,
+    update: impl FnMut(&mut AnimationContext<State, Msg>) + 'a
+) -> Result<()> {
+    let mut frames = FrameLoop::new(&config, backend)?;
+
+    let mut runner = Runner::new(context, frames.initial_size(), Box::new(layout_provider), Box::new(update));
+
+    runner.render();
+
+    match &config.record_path {
+        Some(path) => {
+            let mut recording = RecordingBackend::create(backend, path)?;
+            run_on_backend(&mut runner, &mut frames, receiver, &config, &mut recording)
         }
+        None => run_on_backend(&mut runner, &mut frames, receiver, &config, backend)
+    }
+}
+
+fn run_on_backend<State: AnimationState, Msg: AnimationMessage>(runner: &mut Runner<State, Msg>, frames: &mut FrameLoop, receiver: &EventReceiver<Msg>, config: &AnimationRunConfig, backend: &mut impl Backend) -> Result<()> {
+    let terminal = TerminalGuard::enter(backend, &terminal_modes(config))?;
+
+    let result = drive(runner, frames, receiver, config, &mut *terminal.backend);
 
-        loop {
-            (self.update)(&mut context);
+    result.and(terminal.leave())
+}
+
+fn terminal_modes(config: &AnimationRunConfig) -> TerminalModes {
+    TerminalModes {
+        alternate_screen: config.buffer_type == AnimationBuffer::Alternate,
+        bracketed_paste: config.bracketed_paste,
+        focus_events: config.focus_events
+    }
+}
+
+fn drive<State: AnimationState, Msg: AnimationMessage>(runner: &mut Runner<State, Msg>, frames: &mut FrameLoop, receiver: &EventReceiver<Msg>, config: &AnimationRunConfig, backend: &mut impl Backend) -> Result<()> {
+    'frames: loop {
+        if !runner.update() { break; }
+
+        let Some(frame) = frames.begin_frame(runner, receiver)? else { break };
 
-            let mut should_stop = false;
+        frames.present(runner, backend)?;
 
-            for command in &context.commands {
-                match command {
-                    AnimationCommand::Quit => {
-                        should_stop = true;
-                    }
+        let events = match frame {
+            Frame::Replayed(events) => {
+                // A replay can still be interrupted from the keyboard
+                if let Some(BackendEvent::Key(code, modifiers)) = backend.poll_event(std::time::Duration::ZERO)? {
+                    if runner::is_exit_key(&code, &modifiers) { break; }
                 }
-            }
 
-            if should_stop { break; }
-
-            context.delta_milis = last_time.elapsed().as_secs_f64().clamp(0.000001, f64::MAX) * 1000.0;
-            last_time = std::time::Instant::now();
-            canvas.draw_on_buffer();
-            
-            if crossterm::event::poll(std::time::Duration::from_millis(1))? {
-                match crossterm::event::read() {
-                    Ok(event) => {
-                        if let crossterm::event::Event::Key(KeyEvent { code: crossterm::event::KeyCode::Esc, .. }) = event {
-                            break;
-                        } else if let crossterm::event::Event::Key(KeyEvent { code: crossterm::event::KeyCode::Char('c'), modifiers, .. }) = event {
-                            if modifiers.contains(crossterm::event::KeyModifiers::CONTROL) {
-                                break;
-                            }
-                        } else if let crossterm::event::Event::Resize(columns, rows) = event {
-                            terminal_columns = columns as usize;
-                            terminal_rows = rows as usize;
-        
-                            let bounds = &Size::new(terminal_columns, terminal_rows);
-                            canvas = TextCanvas::create_in_bounds(bounds);
-                            context.pending_events.push(AnimationEvent::Resize(terminal_columns, terminal_rows));
-                        } else if let crossterm::event::Event::Key(e) = event {
-                            context.pending_events.push(AnimationEvent::KeyEvent(e.code, e.modifiers));
-                        }
-                    }
-                    Err(err) => {
-                        crossterm::execute!(stdout, crossterm::terminal::LeaveAlternateScreen, crossterm::style::Print(format!("{err}")), crossterm::terminal::EnterAlternateScreen)?;
-                        break;
-                    }
-                };
+                events
             }
+            Frame::Live => backend.poll_event_or_wake(frames.wait_timeout(runner), receiver.wake())?.into_iter().collect()
+        };
 
-            canvas.clear_with(" ");
-
-            let layout = (self.layout)(&mut context);
-            canvas.render_layout(&layout, &mut context);
-            
-            self.clear_buffer();
-            context.frame_count += 1;
+        for event in events {
+            if !frames.handle_event(runner, event, backend, config)? { break 'frames; }
         }
 
-        Ok(())
+        frames.end_frame(runner);
     }
+
+    frames.finish()
 }
 
-impl AnimatedTextCanvas<()> {
+impl<Msg: AnimationMessage> AnimatedTextCanvas<(), Msg> {
+    #[cfg(feature = "crossterm")]
     pub fn run(&self, config: AnimationRunConfig) -> Result<()> {
         self.run_with_state((), config)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex};
+
+    use crate::backend::memory::MemoryBackend;
+
+    use super::*;
+
+    // The panic hook is global, tests that swap it take turns
+    static PANIC_HOOK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn uninstalling_the_panic_hook_puts_back_the_one_before_it() {
+        let _hook = PANIC_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Tests panicking on other threads reach the hook too, only this thread's panics count
+        static ORIGINAL_CALLS: AtomicUsize = AtomicUsize::new(0);
+        let test_thread = std::thread::current().id();
+
+        let saved = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |_| {
+            if std::thread::current().id() == test_thread { ORIGINAL_CALLS.fetch_add(1, Ordering::SeqCst); }
+        }));
+
+        let restores = Arc::new(AtomicUsize::new(0));
+        let counter = restores.clone();
+        let installed = install_panic_hook(Box::new(move || { counter.fetch_add(1, Ordering::SeqCst); }));
+
+        let _ = std::panic::catch_unwind(|| panic!("while installed"));
+        assert_eq!(restores.load(Ordering::SeqCst), 1);
+        assert_eq!(ORIGINAL_CALLS.load(Ordering::SeqCst), 1);
+
+        // A background thread panicking leaves the terminal to the loop
+        let _ = std::thread::spawn(|| panic!("in the background")).join();
+        assert_eq!(restores.load(Ordering::SeqCst), 1);
+
+        uninstall_panic_hook(installed);
+
+        let _ = std::panic::catch_unwind(|| panic!("after uninstalling"));
+        assert_eq!(restores.load(Ordering::SeqCst), 1);
+        assert_eq!(ORIGINAL_CALLS.load(Ordering::SeqCst), 2);
+
+        std::panic::set_hook(saved);
+    }
+
+    #[test]
+    fn dropping_the_terminal_guard_leaves_the_terminal() {
+        let _hook = PANIC_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut backend = MemoryBackend::new(10, 2);
+
+        let terminal = TerminalGuard::enter(&mut backend, &TerminalModes::default()).unwrap();
+        assert!(terminal.backend.modes().is_some());
+        drop(terminal);
+
+        assert!(backend.modes().is_none());
+    }
+
+    #[test]
+    fn the_terminal_is_left_when_the_loop_fails() {
+        let _hook = PANIC_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut backend = MemoryBackend::new(10, 2);
+        let config = AnimationRunConfig::default().replay_events_from("/nonexistent/events.log");
+
+        let canvas: AnimatedTextCanvas<()> = AnimatedTextCanvas::new(|_| Layout::text("x"));
+
+        assert!(canvas.run_with_backend((), config, &mut backend).is_err());
+        assert!(backend.modes().is_none());
+    }
+
+    #[test]
+    fn the_terminal_is_left_after_the_exit_key() {
+        let _hook = PANIC_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut backend = MemoryBackend::new(10, 2);
+        backend.push_event(BackendEvent::Key(KeyCode::Esc, KeyModifiers::NONE));
+
+        let canvas: AnimatedTextCanvas<()> = AnimatedTextCanvas::new(|_| Layout::text("x"));
+
+        canvas.run_with_backend((), AnimationRunConfig::default(), &mut backend).unwrap();
+
+        assert!(backend.modes().is_none());
+        assert!(backend.frame().to_string().starts_with('x'));
+    }
+
+    #[test]
+    fn focus_events_are_only_asked_for_when_configured() {
+        assert!(!terminal_modes(&AnimationRunConfig::default()).focus_events);
+        assert!(terminal_modes(&AnimationRunConfig::default().with_focus_events()).focus_events);
+    }
+
+    // Keeps typed characters and messages in the order they came in
+    fn logging_canvas() -> AnimatedTextCanvas<String, String> {
+        let mut canvas = AnimatedTextCanvas::new(|context: &AnimationContext<String, String>| Layout::text(&context.state));
+        canvas.set_update(|context| {
+            for event in context.take_events() {
+                match event {
+                    AnimationEvent::KeyEvent(KeyCode::Char(c), _) => context.state.push(c),
+                    AnimationEvent::Message(message) => context.state.push_str(&format!("[{message}]")),
+                    _ => {}
+                }
+            }
+        });
+        canvas.set_message_codec(|message| message.clone(), |text| Some(text.to_string()));
+
+        canvas
+    }
+
+    fn log_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("textyle-{name}-{}.log", std::process::id()))
+    }
+
+    fn logged_run(path: &std::path::Path) -> String {
+        let mut backend = MemoryBackend::new(20, 1);
+        for c in "ab".chars() {
+            backend.push_event(BackendEvent::Key(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        // One more frame, so the last character gets drawn before leaving
+        backend.push_event(BackendEvent::Key(KeyCode::Null, KeyModifiers::NONE));
+        backend.push_event(BackendEvent::Key(KeyCode::Esc, KeyModifiers::NONE));
+
+        let canvas = logging_canvas();
+        canvas.event_sender().send("bg".to_string());
+
+        canvas.run_with_backend(String::new(), AnimationRunConfig::default().log_events_to(path), &mut backend).unwrap();
+
+        backend.frame().to_string().trim_end().to_string()
+    }
+
+    #[test]
+    fn replays_see_the_logged_messages_instead_of_live_ones() {
+        let _hook = PANIC_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let path = log_path("replay-loop");
+        let shown = logged_run(&path);
+        assert_eq!(shown, "[bg]ab");
+
+        let canvas = logging_canvas();
+        canvas.event_sender().send("late".to_string());
+
+        // A terminal of another size still replays at the size the log was written at
+        let mut backend = MemoryBackend::new(30, 3);
+        canvas.run_with_backend(String::new(), AnimationRunConfig::default().replay_events_from(&path), &mut backend).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(backend.frame().size(), &Size::new(20, 1));
+        assert_eq!(backend.frame().to_string().trim_end(), shown);
+    }
+
+    #[test]
+    fn the_test_driver_replays_logs_too() {
+        let _hook = PANIC_HOOK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let path = log_path("replay-driver");
+        let shown = logged_run(&path);
+        let log = crate::recording::events::EventLog::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let canvas = logging_canvas();
+        let mut driver = driver::TestDriver::new(&canvas, String::new(), Size::new(20, 1));
+
+        assert!(driver.replay(&log).unwrap());
+        driver.step(16.0);
+
+        assert_eq!(driver.context().state, "[bg]ab");
+        assert_eq!(driver.canvas().to_string().trim_end(), shown);
+    }
+}
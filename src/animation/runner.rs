@@ -0,0 +1,80 @@
+use crate::{canvas::TextCanvas, layout::{geometry::Size, Layout}, rendering::state::RenderState};
+
+use super::{AnimationCommand, AnimationContext, AnimationEvent, AnimationMessage, AnimationState, KeyCode, KeyModifiers};
+
+pub(crate) type LayoutFn<'a, State, Msg> = Box<dyn Fn(&AnimationContext<State, Msg>)->Layout<AnimationContext<State, Msg>> + 'a>;
+pub(crate) type UpdateFn<'a, State, Msg> = Box<dyn FnMut(&mut AnimationContext<State, Msg>) + 'a>;
+
+// The frame logic shared by the terminal loop and the headless test driver.
+// Anything that talks to the actual terminal stays out of here.
+pub(crate) struct Runner<'a, State: AnimationState, Msg: AnimationMessage> {
+    pub(crate) context: AnimationContext<State, Msg>,
+    pub(crate) canvas: TextCanvas,
+    render_state: RenderState<AnimationContext<State, Msg>>,
+    layout: LayoutFn<'a, State, Msg>,
+    update: UpdateFn<'a, State, Msg>
+}
+
+impl<'a, State: AnimationState, Msg: AnimationMessage> Runner<'a, State, Msg> {
+    pub(crate) fn new(mut context: AnimationContext<State, Msg>, size: &Size, layout: LayoutFn<'a, State, Msg>, update: UpdateFn<'a, State, Msg>) -> Self {
+        context.viewport = size.clone();
+
+        Runner {
+            context,
+            canvas: TextCanvas::create_in_bounds(size),
+            render_state: RenderState::new(),
+            layout,
+            update
+        }
+    }
+
+    // Returns false once the update asked to quit
+    pub(crate) fn update(&mut self) -> bool {
+        (self.update)(&mut self.context);
+
+        !self.context.commands.iter().any(|c| matches!(c, AnimationCommand::Quit))
+    }
+
+    pub(crate) fn advance(&mut self, delta_milis: f64, messages: Vec<Msg>) {
+        self.context.advance_clock(delta_milis);
+        self.context.receive_messages(messages);
+    }
+
+    pub(crate) fn push_event(&mut self, event: AnimationEvent<Msg>) {
+        if let AnimationEvent::Resize(columns, rows) = event {
+            self.canvas = TextCanvas::create(columns, rows);
+            self.context.viewport = Size::new(columns, rows);
+        }
+
+        self.context.pending_events.push(event);
+    }
+
+    pub(crate) fn render(&mut self) {
+        self.canvas.clear_with(" ");
+
+        self.render_state.advance(self.context.delta_milis);
+
+        self.render_state.start_focus_scan(self.context.focus.focused());
+
+        let layout = (self.layout)(&self.context);
+        self.canvas.render_layout_with_state(&layout, &mut self.context, &mut self.render_state);
+
+        self.context.focus.set_order(self.render_state.focus_order().to_vec());
+        self.context.key_handlers = self.render_state.key_handlers().clone();
+    }
+}
+
+// Ctrl+Z, which raw mode keeps from reaching the shell as SIGTSTP. Only taken from the
+// animation when the backend can suspend.
+pub(crate) fn is_suspend_key(code: &KeyCode, modifiers: &KeyModifiers) -> bool {
+    matches!(code, KeyCode::Char('z')) && modifiers.contains(KeyModifiers::CONTROL)
+}
+
+// Keys that always end the loop, before the update gets to see them
+pub(crate) fn is_exit_key(code: &KeyCode, modifiers: &KeyModifiers) -> bool {
+    match code {
+        KeyCode::Esc => true,
+        KeyCode::Char('c') => modifiers.contains(KeyModifiers::CONTROL),
+        _ => false
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use super::{AnimationEvent, AnimationMessage};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerHandle(usize);
+
+#[derive(Clone)]
+struct ScheduledEvent<Msg: AnimationMessage> {
+    handle: TimerHandle,
+    due_milis: f64,
+    interval_milis: Option<f64>,
+    event: AnimationEvent<Msg>
+}
+
+#[derive(Clone)]
+pub struct Timers<Msg: AnimationMessage> {
+    next_id: usize,
+    scheduled: Vec<ScheduledEvent<Msg>>
+}
+
+impl<Msg: AnimationMessage> Default for Timers<Msg> {
+    fn default() -> Self {
+        Timers { next_id: 0, scheduled: vec![] }
+    }
+}
+
+impl<Msg: AnimationMessage> Timers<Msg> {
+    pub(crate) fn schedule(&mut self, now_milis: f64, delay: Duration, interval: Option<Duration>, event: AnimationEvent<Msg>) -> TimerHandle {
+        let handle = TimerHandle(self.next_id);
+        self.next_id += 1;
+
+        self.scheduled.push(ScheduledEvent {
+            handle,
+            due_milis: now_milis + delay.as_secs_f64() * 1000.0,
+            // A zero interval would fire on every frame forever, so it's clamped to at least a millisecond.
+            interval_milis: interval.map(|i| (i.as_secs_f64() * 1000.0).max(1.0)),
+            event
+        });
+
+        handle
+    }
+
+    pub fn cancel(&mut self, handle: TimerHandle) -> bool {
+        let count = self.scheduled.len();
+        self.scheduled.retain(|s| s.handle != handle);
+
+        self.scheduled.len() != count
+    }
+
+    pub fn is_active(&self, handle: TimerHandle) -> bool {
+        self.scheduled.iter().any(|s| s.handle == handle)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty()
+    }
+
+    pub fn next_due_in(&self, now_milis: f64) -> Option<Duration> {
+        self.scheduled.iter()
+            .map(|s| (s.due_milis - now_milis).max(0.0))
+            .min_by(|a, b| a.total_cmp(b))
+            .map(|milis| Duration::from_secs_f64(milis / 1000.0))
+    }
+
+    pub(crate) fn take_due(&mut self, now_milis: f64) -> Vec<AnimationEvent<Msg>> {
+        let mut due = self.scheduled.iter()
+            .filter(|s| s.due_milis <= now_milis)
+            .map(|s| (s.due_milis, s.handle.0, s.event.clone()))
+            .collect::<Vec<_>>();
+
+        // Events that were due earlier go first, ties are broken by scheduling order.
+        due.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        self.scheduled.retain_mut(|s| {
+            if s.due_milis > now_milis { return true; }
+
+            match s.interval_milis {
+                Some(interval) => {
+                    // Missed ticks are skipped instead of firing in a burst after a slow frame.
+                    while s.due_milis <= now_milis {
+                        s.due_milis += interval;
+                    }
+
+                    true
+                }
+                None => false
+            }
+        });
+
+        due.into_iter().map(|(_, _, event)| event).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks(events: Vec<AnimationEvent<u32>>) -> Vec<u32> {
+        events.into_iter()
+            .filter_map(|e| match e { AnimationEvent::Message(n) => Some(n), _ => None })
+            .collect()
+    }
+
+    #[test]
+    fn a_delayed_event_fires_once_when_due() {
+        let mut timers = Timers::default();
+        timers.schedule(0.0, Duration::from_millis(100), None, AnimationEvent::Message(1));
+
+        assert!(timers.take_due(99.0).is_empty());
+        assert_eq!(ticks(timers.take_due(100.0)), vec![1]);
+        assert!(timers.take_due(1000.0).is_empty());
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn a_repeating_event_skips_the_ticks_a_slow_frame_missed() {
+        let mut timers = Timers::default();
+        let handle = timers.schedule(0.0, Duration::from_millis(10), Some(Duration::from_millis(10)), AnimationEvent::Message(1));
+
+        assert_eq!(ticks(timers.take_due(10.0)), vec![1]);
+        assert_eq!(ticks(timers.take_due(55.0)), vec![1]);
+        assert_eq!(timers.next_due_in(55.0), Some(Duration::from_millis(5)));
+        assert!(timers.is_active(handle));
+    }
+
+    #[test]
+    fn due_events_come_out_in_the_order_they_were_due() {
+        let mut timers = Timers::default();
+        timers.schedule(0.0, Duration::from_millis(20), None, AnimationEvent::Message(2));
+        timers.schedule(0.0, Duration::from_millis(10), None, AnimationEvent::Message(1));
+        timers.schedule(0.0, Duration::from_millis(20), None, AnimationEvent::Message(3));
+
+        assert_eq!(ticks(timers.take_due(30.0)), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn cancelled_timers_never_fire() {
+        let mut timers = Timers::default();
+        let handle = timers.schedule(0.0, Duration::from_millis(10), None, AnimationEvent::Message(1));
+
+        assert!(timers.cancel(handle));
+        assert!(!timers.cancel(handle));
+        assert!(timers.take_due(10.0).is_empty());
+        assert_eq!(timers.next_due_in(0.0), None);
+    }
+
+    #[test]
+    fn an_overdue_timer_is_due_right_away() {
+        let mut timers = Timers::default();
+        timers.schedule(0.0, Duration::from_millis(10), None, AnimationEvent::Message(1));
+
+        assert_eq!(timers.next_due_in(4.0), Some(Duration::from_millis(6)));
+        assert_eq!(timers.next_due_in(50.0), Some(Duration::ZERO));
+    }
+}
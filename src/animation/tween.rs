@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use crate::layout::geometry::Rect;
+
+// Layouts ease their animations and transitions too, so easing lives outside of the animation module
+pub use crate::easing::Easing;
+
+pub trait Interpolate {
+    fn interpolate(&self, to: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Interpolate for usize {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        (*self as f64).interpolate(&(*to as f64), t).round().max(0.0) as usize
+    }
+}
+
+impl Interpolate for i64 {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        (*self as f64).interpolate(&(*to as f64), t).round() as i64
+    }
+}
+
+impl Interpolate for Rect {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        Rect::new(
+            self.x.interpolate(&to.x, t),
+            self.y.interpolate(&to.y, t),
+            self.width.interpolate(&to.width, t),
+            self.height.interpolate(&to.height, t)
+        )
+    }
+}
+
+// RGB triplet
+impl Interpolate for (u8, u8, u8) {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        let channel = |a: u8, b: u8| (a as f64).interpolate(&(b as f64), t).round().clamp(0.0, 255.0) as u8;
+
+        (channel(self.0, to.0), channel(self.1, to.1), channel(self.2, to.2))
+    }
+}
+
+#[cfg(feature = "crossterm")]
+impl Interpolate for crossterm::style::Color {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        use crossterm::style::Color;
+
+        match (self, to) {
+            (Color::Rgb { r, g, b }, Color::Rgb { r: r2, g: g2, b: b2 }) => {
+                let (r, g, b) = (*r, *g, *b).interpolate(&(*r2, *g2, *b2), t);
+                Color::Rgb { r, g, b }
+            }
+            // Palette colours can't be blended, they switch halfway through
+            _ => if t < 0.5 { *self } else { *to }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Animated<T: Interpolate + Clone> {
+    from: T,
+    to: T,
+    easing: Easing,
+    duration_milis: f64,
+    elapsed_milis: f64
+}
+
+impl<T: Interpolate + Clone> Animated<T> {
+    pub fn new(value: T) -> Self {
+        Animated {
+            from: value.clone(),
+            to: value,
+            easing: Easing::Linear,
+            duration_milis: 0.0,
+            elapsed_milis: 0.0
+        }
+    }
+
+    // Starts a new animation from wherever the value currently is, so retargeting
+    // halfway through doesn't make it jump.
+    pub fn animate_to(&mut self, target: T, duration: Duration, easing: Easing) {
+        self.from = self.value();
+        self.to = target;
+        self.easing = easing;
+        self.duration_milis = duration.as_secs_f64() * 1000.0;
+        self.elapsed_milis = 0.0;
+    }
+
+    pub fn set(&mut self, value: T) {
+        self.from = value.clone();
+        self.to = value;
+        self.duration_milis = 0.0;
+        self.elapsed_milis = 0.0;
+    }
+
+    pub fn tick(&mut self, delta_milis: f64) {
+        self.elapsed_milis = (self.elapsed_milis + delta_milis).min(self.duration_milis);
+    }
+
+    pub fn progress(&self) -> f64 {
+        if self.duration_milis <= 0.0 { return 1.0; }
+
+        self.elapsed_milis / self.duration_milis
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    pub fn value(&self) -> T {
+        if self.is_finished() { return self.to.clone(); }
+
+        self.from.interpolate(&self.to, self.easing.apply(self.progress()))
+    }
+
+    pub fn target(&self) -> &T {
+        &self.to
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reaches_the_target_after_the_duration() {
+        let mut value = Animated::new(0.0);
+        value.animate_to(10.0, Duration::from_millis(100), Easing::Linear);
+
+        value.tick(50.0);
+        assert_eq!(value.value(), 5.0);
+
+        value.tick(500.0);
+        assert_eq!(value.value(), 10.0);
+        assert!(value.is_finished());
+    }
+
+    #[test]
+    fn retargeting_starts_from_where_the_value_is() {
+        let mut value = Animated::new(0.0);
+        value.animate_to(10.0, Duration::from_millis(100), Easing::Linear);
+        value.tick(50.0);
+
+        value.animate_to(0.0, Duration::from_millis(100), Easing::Linear);
+        assert_eq!(value.value(), 5.0);
+
+        value.tick(50.0);
+        assert_eq!(value.value(), 2.5);
+    }
+
+    #[test]
+    fn setting_skips_the_animation() {
+        let mut value = Animated::new(0usize);
+        value.animate_to(10, Duration::from_millis(100), Easing::EaseIn);
+        value.set(3);
+
+        assert_eq!(value.value(), 3);
+        assert_eq!(value.target(), &3);
+    }
+
+    #[test]
+    fn rects_and_colours_interpolate_per_field() {
+        let from = Rect::new(0, 0, 10, 2);
+        let to = Rect::new(10, -4, 20, 4);
+        assert_eq!(from.interpolate(&to, 0.5), Rect::new(5, -2, 15, 3));
+
+        assert_eq!((0u8, 100u8, 255u8).interpolate(&(255, 100, 0), 0.5), (128, 100, 128));
+    }
+}
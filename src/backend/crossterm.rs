@@ -0,0 +1,333 @@
+use std::{io::{IsTerminal, Stdout, Write}, time::Duration};
+#[cfg(unix)]
+use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+
+use anyhow::Result;
+use crossterm::{cursor, event, queue, execute, style::Print, terminal};
+
+use crate::{canvas::TextCanvas, layout::geometry::Size};
+
+use super::{keys::{KeyCode, KeyModifiers}, wake::Wake, Backend, BackendEvent, TerminalModes};
+
+pub struct CrosstermBackend {
+    stdout: Stdout,
+    modes: Option<TerminalModes>,
+    drawn_size: Option<Size>,
+    // Whether stdout was a terminal when it was entered
+    attached: bool,
+    #[cfg(unix)]
+    signals: Option<JobSignals>
+}
+
+// Set by signal handlers registered while the terminal is entered
+#[cfg(unix)]
+struct JobSignals {
+    stopped: Arc<AtomicBool>,
+    continued: Arc<AtomicBool>,
+    handlers: [signal_hook::SigId; 2]
+}
+
+#[cfg(unix)]
+impl JobSignals {
+    fn register() -> Result<Self> {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let continued = Arc::new(AtomicBool::new(false));
+
+        let handlers = [
+            signal_hook::flag::register(signal_hook::consts::SIGTSTP, stopped.clone())?,
+            signal_hook::flag::register(signal_hook::consts::SIGCONT, continued.clone())?
+        ];
+
+        Ok(JobSignals { stopped, continued, handlers })
+    }
+
+    fn unregister(self) {
+        for handler in self.handlers {
+            signal_hook::low_level::unregister(handler);
+        }
+    }
+}
+
+impl Default for CrosstermBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CrosstermBackend {
+    pub fn new() -> Self {
+        CrosstermBackend {
+            stdout: std::io::stdout(),
+            modes: None,
+            drawn_size: None,
+            attached: true,
+            #[cfg(unix)]
+            signals: None
+        }
+    }
+}
+
+fn restore_terminal(modes: &TerminalModes) {
+    let _ = terminal::disable_raw_mode();
+
+    let mut stdout = std::io::stdout();
+    let _ = execute!(
+        stdout,
+        event::DisableMouseCapture,
+        cursor::Show
+    );
+
+    if modes.bracketed_paste {
+        let _ = execute!(stdout, event::DisableBracketedPaste);
+    }
+
+    if modes.focus_events {
+        let _ = execute!(stdout, event::DisableFocusChange);
+    }
+
+    if modes.alternate_screen {
+        let _ = execute!(stdout, terminal::LeaveAlternateScreen);
+    }
+}
+
+// Mouse events aren't surfaced
+pub(crate) fn backend_event(event: event::Event) -> Option<BackendEvent> {
+    match event {
+        event::Event::Key(e) => Some(BackendEvent::Key(e.code.into(), e.modifiers.into())),
+        event::Event::Resize(columns, rows) => Some(BackendEvent::Resize(columns as usize, rows as usize)),
+        event::Event::Paste(text) => Some(BackendEvent::Paste(text)),
+        event::Event::FocusGained => Some(BackendEvent::FocusGained),
+        event::Event::FocusLost => Some(BackendEvent::FocusLost),
+        _ => None
+    }
+}
+
+impl Backend for CrosstermBackend {
+    fn size(&self) -> Result<Size> {
+        let (columns, rows) = terminal::size()?;
+
+        Ok(Size::new(columns as usize, rows as usize))
+    }
+
+    fn draw(&mut self, canvas: &TextCanvas) -> Result<()> {
+        let size = canvas.size();
+
+        // Terminals reflow whatever was on screen when they get resized, so the first frame
+        // at a new size starts from a blank screen instead of drawing over the leftovers.
+        if self.drawn_size.as_ref() != Some(size) {
+            queue!(self.stdout, terminal::Clear(terminal::ClearType::All))?;
+            self.drawn_size = Some(size.clone());
+        }
+
+        queue!(self.stdout, cursor::MoveTo(0, 0))?;
+
+        for y in 0..size.height {
+            if y > 0 {
+                queue!(self.stdout, cursor::MoveToNextLine(1))?;
+            }
+
+            for x in 0..size.width {
+                let grapheme = canvas.get_at(x, y).unwrap_or(" ");
+                queue!(self.stdout, Print(grapheme))?;
+            }
+        }
+
+        match canvas.cursor() {
+            Some((x, y)) => queue!(self.stdout, cursor::MoveTo(x as u16, y as u16), cursor::Show)?,
+            None => queue!(self.stdout, cursor::Hide)?
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.stdout.flush()?;
+
+        Ok(())
+    }
+
+    fn poll_event(&mut self, timeout: Duration) -> Result<Option<BackendEvent>> {
+        if let Some(event) = self.take_signal()? { return Ok(Some(event)); }
+
+        match event::poll(timeout) {
+            Ok(false) => return Ok(None),
+            Ok(true) => {}
+            // Detached there may be no terminal to read from at all, the animation still runs
+            Err(_) if !self.attached => {
+                std::thread::sleep(timeout);
+                return Ok(None);
+            }
+            Err(error) => return Err(error.into())
+        }
+
+        Ok(backend_event(event::read()?))
+    }
+
+    #[cfg(unix)]
+    fn poll_event_or_wake(&mut self, timeout: Duration, wake: &Wake) -> Result<Option<BackendEvent>> {
+        if let Some(event) = self.take_signal()? { return Ok(Some(event)); }
+        if !self.attached { return self.poll_event(timeout); }
+
+        // Whatever crossterm already read goes first
+        if event::poll(Duration::ZERO)? { return Ok(backend_event(event::read()?)); }
+        if wake.is_signalled() { return Ok(None); }
+
+        // Crossterm reads from /dev/tty when stdin isn't the terminal, there's no waiting on both then
+        let is_tty = unsafe { libc::isatty(libc::STDIN_FILENO) } == 1;
+        let Some(wake_fd) = wake.fd().filter(|_| is_tty) else { return self.poll_event(timeout) };
+
+        let mut fds = [
+            libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 },
+            libc::pollfd { fd: wake_fd, events: libc::POLLIN, revents: 0 }
+        ];
+
+        // Rounded up, so a timer isn't polled for just before it's due
+        let milis = timeout.as_micros().div_ceil(1000).min(libc::c_int::MAX as u128) as libc::c_int;
+        unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, milis); }
+
+        if fds[1].revents & libc::POLLIN != 0 { return Ok(None); }
+
+        // Input, a signal that cut the wait short, or the timeout. Crossterm sorts out which.
+        self.poll_event(Duration::ZERO)
+    }
+
+    #[cfg(unix)]
+    fn take_signal(&mut self) -> Result<Option<BackendEvent>> {
+        let Some(signals) = &self.signals else { return Ok(None) };
+
+        if signals.stopped.swap(false, Ordering::SeqCst) { return Ok(Some(BackendEvent::Suspend)); }
+
+        if signals.continued.swap(false, Ordering::SeqCst) {
+            // Stopped by something we couldn't catch, like SIGSTOP. Whatever had the terminal
+            // meanwhile may have reset it.
+            if let Some(modes) = self.modes.clone() {
+                self.enter(&modes)?;
+            }
+
+            return Ok(Some(BackendEvent::Resume));
+        }
+
+        Ok(None)
+    }
+
+    fn enter(&mut self, modes: &TerminalModes) -> Result<()> {
+        // Output that goes to a file or a pipe only gets the frames. There's no terminal to put
+        // in raw mode, and the viewport falls back to the configured size.
+        self.attached = self.stdout.is_terminal();
+        let modes = &if self.attached { modes.clone() } else { TerminalModes::default() };
+
+        if self.attached {
+            terminal::enable_raw_mode()?;
+        }
+
+        if modes.alternate_screen {
+            execute!(self.stdout, terminal::EnterAlternateScreen)?;
+        }
+
+        if modes.bracketed_paste {
+            execute!(self.stdout, event::EnableBracketedPaste)?;
+        }
+
+        if modes.focus_events {
+            execute!(self.stdout, event::EnableFocusChange)?;
+        }
+
+        execute!(
+            self.stdout,
+            cursor::Hide,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+
+        self.modes = Some(modes.clone());
+        self.drawn_size = None;
+
+        #[cfg(unix)]
+        if self.signals.is_none() {
+            self.signals = Some(JobSignals::register()?);
+        }
+
+        Ok(())
+    }
+
+    fn leave(&mut self) -> Result<()> {
+        #[cfg(unix)]
+        if let Some(signals) = self.signals.take() {
+            signals.unregister();
+        }
+
+        if let Some(modes) = self.modes.take() {
+            restore_terminal(&modes);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn suspend(&mut self) -> Result<()> {
+        let Some(modes) = self.modes.take() else { return Ok(()) };
+
+        restore_terminal(&modes);
+
+        // Our SIGTSTP handler would only set a flag, so this stops the process the way the
+        // default one does, right here until a SIGCONT resumes it
+        signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)?;
+
+        self.enter(&modes)?;
+
+        // Already handled, the SIGCONT that got us here isn't another resume
+        if let Some(signals) = &self.signals {
+            signals.continued.store(false, Ordering::SeqCst);
+        }
+
+        Ok(())
+    }
+
+    fn can_suspend(&self) -> bool {
+        cfg!(unix)
+    }
+
+    fn panic_restore(&self) -> Box<dyn Fn() + Send + Sync> {
+        let modes = self.modes.clone().unwrap_or_default();
+
+        Box::new(move || restore_terminal(&modes))
+    }
+}
+
+impl From<event::KeyCode> for KeyCode {
+    fn from(code: event::KeyCode) -> Self {
+        match code {
+            event::KeyCode::Backspace => KeyCode::Backspace,
+            event::KeyCode::Enter => KeyCode::Enter,
+            event::KeyCode::Left => KeyCode::Left,
+            event::KeyCode::Right => KeyCode::Right,
+            event::KeyCode::Up => KeyCode::Up,
+            event::KeyCode::Down => KeyCode::Down,
+            event::KeyCode::Home => KeyCode::Home,
+            event::KeyCode::End => KeyCode::End,
+            event::KeyCode::PageUp => KeyCode::PageUp,
+            event::KeyCode::PageDown => KeyCode::PageDown,
+            event::KeyCode::Tab => KeyCode::Tab,
+            event::KeyCode::BackTab => KeyCode::BackTab,
+            event::KeyCode::Delete => KeyCode::Delete,
+            event::KeyCode::Insert => KeyCode::Insert,
+            event::KeyCode::F(n) => KeyCode::F(n),
+            event::KeyCode::Char(c) => KeyCode::Char(c),
+            event::KeyCode::Esc => KeyCode::Esc,
+            _ => KeyCode::Null
+        }
+    }
+}
+
+impl From<event::KeyModifiers> for KeyModifiers {
+    fn from(modifiers: event::KeyModifiers) -> Self {
+        let mut result = KeyModifiers::NONE;
+
+        if modifiers.contains(event::KeyModifiers::SHIFT) { result |= KeyModifiers::SHIFT; }
+        if modifiers.contains(event::KeyModifiers::CONTROL) { result |= KeyModifiers::CONTROL; }
+        if modifiers.contains(event::KeyModifiers::ALT) { result |= KeyModifiers::ALT; }
+        if modifiers.contains(event::KeyModifiers::SUPER) { result |= KeyModifiers::SUPER; }
+
+        result
+    }
+}
@@ -0,0 +1,72 @@
+use std::ops::{BitOr, BitOrAssign};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    Backspace,
+    Enter,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Tab,
+    BackTab,
+    Delete,
+    Insert,
+    F(u8),
+    Char(char),
+    Null,
+    Esc
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KeyModifiers(u8);
+
+impl KeyModifiers {
+    pub const NONE: KeyModifiers = KeyModifiers(0);
+    pub const SHIFT: KeyModifiers = KeyModifiers(1);
+    pub const CONTROL: KeyModifiers = KeyModifiers(1 << 1);
+    pub const ALT: KeyModifiers = KeyModifiers(1 << 2);
+    pub const SUPER: KeyModifiers = KeyModifiers(1 << 3);
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub fn from_bits(bits: u8) -> KeyModifiers {
+        KeyModifiers(bits)
+    }
+
+    pub fn contains(&self, other: KeyModifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn insert(&mut self, other: KeyModifiers) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: KeyModifiers) {
+        self.0 &= !other.0;
+    }
+}
+
+impl BitOr for KeyModifiers {
+    type Output = KeyModifiers;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        KeyModifiers(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for KeyModifiers {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.insert(rhs);
+    }
+}
@@ -0,0 +1,83 @@
+use std::{collections::VecDeque, time::Duration};
+
+use anyhow::Result;
+
+use crate::{canvas::TextCanvas, layout::geometry::Size};
+
+use super::{Backend, BackendEvent, TerminalModes};
+
+// A terminal that only exists in memory. Events are queued up front and
+// the last drawn frame can be inspected afterwards.
+pub struct MemoryBackend {
+    size: Size,
+    frame: TextCanvas,
+    events: VecDeque<BackendEvent>,
+    modes: Option<TerminalModes>,
+    draw_count: usize
+}
+
+impl MemoryBackend {
+    pub fn new(width: usize, height: usize) -> Self {
+        MemoryBackend {
+            size: Size::new(width, height),
+            frame: TextCanvas::create(width, height),
+            events: VecDeque::new(),
+            modes: None,
+            draw_count: 0
+        }
+    }
+
+    pub fn push_event(&mut self, event: BackendEvent) {
+        if let BackendEvent::Resize(width, height) = event {
+            self.size = Size::new(width, height);
+        }
+
+        self.events.push_back(event);
+    }
+
+    pub fn frame(&self) -> &TextCanvas {
+        &self.frame
+    }
+
+    pub fn draw_count(&self) -> usize {
+        self.draw_count
+    }
+
+    // The modes the loop entered, or None when outside of it
+    pub fn modes(&self) -> Option<&TerminalModes> {
+        self.modes.as_ref()
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn size(&self) -> Result<Size> {
+        Ok(self.size.clone())
+    }
+
+    fn draw(&mut self, canvas: &TextCanvas) -> Result<()> {
+        self.frame = canvas.clone();
+        self.draw_count += 1;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn poll_event(&mut self, _timeout: Duration) -> Result<Option<BackendEvent>> {
+        Ok(self.events.pop_front())
+    }
+
+    fn enter(&mut self, modes: &TerminalModes) -> Result<()> {
+        self.modes = Some(modes.clone());
+
+        Ok(())
+    }
+
+    fn leave(&mut self) -> Result<()> {
+        self.modes = None;
+
+        Ok(())
+    }
+}
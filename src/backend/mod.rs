@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::{canvas::TextCanvas, layout::geometry::Size};
+
+pub mod keys;
+pub mod memory;
+pub mod writer;
+pub mod wake;
+#[cfg(feature = "crossterm")]
+pub mod crossterm;
+
+use keys::{KeyCode, KeyModifiers};
+use wake::Wake;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BackendEvent {
+    Key(KeyCode, KeyModifiers),
+    Resize(usize, usize),
+    Paste(String),
+    FocusGained,
+    FocusLost,
+    // The process got SIGTSTP from outside, e.g. `kill -TSTP`, and should suspend
+    Suspend,
+    // The process was continued after something else stopped it. The terminal has been
+    // entered again, whatever was on screen needs a full redraw.
+    Resume
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TerminalModes {
+    pub alternate_screen: bool,
+    pub bracketed_paste: bool,
+    pub focus_events: bool
+}
+
+pub trait Backend {
+    fn size(&self) -> Result<Size>;
+
+    // Queues every cell of the canvas, nothing is guaranteed to show up before `flush`
+    fn draw(&mut self, canvas: &TextCanvas) -> Result<()>;
+    fn flush(&mut self) -> Result<()>;
+
+    fn poll_event(&mut self, timeout: Duration) -> Result<Option<BackendEvent>>;
+
+    // Like `poll_event`, but returns None early once `wake` is signalled. Backends that can't
+    // wait on both only notice a wake that came before they started waiting.
+    fn poll_event_or_wake(&mut self, timeout: Duration, wake: &Wake) -> Result<Option<BackendEvent>> {
+        if wake.is_signalled() { return Ok(None); }
+
+        self.poll_event(timeout)
+    }
+
+    // A `Suspend` or `Resume` for the job control signals that came in since the last call.
+    // Polling for events already returns them, this is for loops that read input elsewhere.
+    fn take_signal(&mut self) -> Result<Option<BackendEvent>> {
+        Ok(None)
+    }
+
+    fn enter(&mut self, modes: &TerminalModes) -> Result<()>;
+    fn leave(&mut self) -> Result<()>;
+
+    // Hands the terminal back to the shell as if the process got SIGTSTP, returning once it's
+    // continued. Backends that aren't attached to a terminal have nothing to suspend.
+    fn suspend(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    // Whether `suspend` does anything. When it doesn't, Ctrl+Z is just another key for the animation.
+    fn can_suspend(&self) -> bool {
+        false
+    }
+
+    // Undoes `enter` from a panic hook, where the backend itself can't be borrowed.
+    fn panic_restore(&self) -> Box<dyn Fn() + Send + Sync> {
+        Box::new(|| {})
+    }
+}
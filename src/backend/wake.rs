@@ -0,0 +1,138 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Cuts a backend's wait for input short, e.g. when a message is sent to the loop from another
+// thread. It stays signalled until it's taken.
+pub struct Wake {
+    signalled: AtomicBool,
+    // Read and write ends of a pipe that has a byte in it while signalled, for waiting on
+    // along with the terminal
+    #[cfg(all(unix, feature = "crossterm"))]
+    pipe: Option<[libc::c_int; 2]>
+}
+
+impl Default for Wake {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Wake {
+    pub fn new() -> Self {
+        Wake {
+            signalled: AtomicBool::new(false),
+            #[cfg(all(unix, feature = "crossterm"))]
+            pipe: open_pipe()
+        }
+    }
+
+    pub fn signal(&self) {
+        // When it was already signalled, the pipe already has a byte in it
+        if !self.signalled.swap(true, Ordering::SeqCst) {
+            self.fill_pipe();
+        }
+    }
+
+    pub fn is_signalled(&self) -> bool {
+        self.signalled.load(Ordering::SeqCst)
+    }
+
+    // Returns whether it was signalled, and clears it
+    pub fn take(&self) -> bool {
+        // Drained before clearing: a signal in between finds it still set and leaves the pipe
+        // empty, one after refills it
+        self.drain_pipe();
+
+        self.signalled.swap(false, Ordering::SeqCst)
+    }
+
+    #[cfg(all(unix, feature = "crossterm"))]
+    fn fill_pipe(&self) {
+        if let Some([_, write]) = self.pipe {
+            unsafe { libc::write(write, [1u8].as_ptr() as *const libc::c_void, 1); }
+        }
+    }
+
+    #[cfg(all(unix, feature = "crossterm"))]
+    fn drain_pipe(&self) {
+        if let Some([read, _]) = self.pipe {
+            let mut buffer = [0u8; 64];
+            while unsafe { libc::read(read, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) } > 0 {}
+        }
+    }
+
+    #[cfg(not(all(unix, feature = "crossterm")))]
+    fn fill_pipe(&self) {}
+
+    #[cfg(not(all(unix, feature = "crossterm")))]
+    fn drain_pipe(&self) {}
+
+    // Becomes readable while signalled
+    #[cfg(all(unix, feature = "crossterm"))]
+    pub(crate) fn fd(&self) -> Option<libc::c_int> {
+        self.pipe.map(|[read, _]| read)
+    }
+}
+
+#[cfg(all(unix, feature = "crossterm"))]
+fn open_pipe() -> Option<[libc::c_int; 2]> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 { return None; }
+
+    // Neither end may block: a full pipe already wakes, an empty one has nothing to drain
+    for fd in fds {
+        unsafe {
+            libc::fcntl(fd, libc::F_SETFL, libc::fcntl(fd, libc::F_GETFL) | libc::O_NONBLOCK);
+            libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+
+    Some(fds)
+}
+
+#[cfg(all(unix, feature = "crossterm"))]
+impl Drop for Wake {
+    fn drop(&mut self) {
+        if let Some(fds) = self.pipe {
+            for fd in fds {
+                unsafe { libc::close(fd); }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stays_signalled_until_taken() {
+        let wake = Wake::new();
+        assert!(!wake.is_signalled());
+
+        wake.signal();
+        wake.signal();
+        assert!(wake.is_signalled());
+
+        assert!(wake.take());
+        assert!(!wake.is_signalled());
+        assert!(!wake.take());
+    }
+
+    #[cfg(all(unix, feature = "crossterm"))]
+    #[test]
+    fn the_pipe_is_readable_only_while_signalled() {
+        let wake = Wake::new();
+        let readable = |wake: &Wake| {
+            let mut fd = libc::pollfd { fd: wake.fd().unwrap(), events: libc::POLLIN, revents: 0 };
+            unsafe { libc::poll(&mut fd, 1, 0) == 1 }
+        };
+
+        assert!(!readable(&wake));
+
+        wake.signal();
+        assert!(readable(&wake));
+
+        wake.take();
+        assert!(!readable(&wake));
+    }
+}
@@ -0,0 +1,157 @@
+use std::{io::Write, time::Duration};
+
+use anyhow::Result;
+
+use crate::{canvas::TextCanvas, layout::geometry::Size};
+
+use super::{Backend, BackendEvent, TerminalModes};
+
+const ENTER_ALTERNATE_SCREEN: &str = "\x1b[?1049h";
+const LEAVE_ALTERNATE_SCREEN: &str = "\x1b[?1049l";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const ENABLE_BRACKETED_PASTE: &str = "\x1b[?2004h";
+const DISABLE_BRACKETED_PASTE: &str = "\x1b[?2004l";
+const ENABLE_FOCUS_EVENTS: &str = "\x1b[?1004h";
+const DISABLE_FOCUS_EVENTS: &str = "\x1b[?1004l";
+const MOVE_HOME: &str = "\x1b[H";
+
+// Output-only backend that writes plain ANSI escape sequences to any writer.
+// It never produces input, polling just waits out the timeout.
+pub struct WriterBackend<W: Write> {
+    writer: W,
+    size: Size,
+    modes: Option<TerminalModes>
+}
+
+impl<W: Write> WriterBackend<W> {
+    pub fn new(writer: W, width: usize, height: usize) -> Self {
+        WriterBackend { writer, size: Size::new(width, height), modes: None }
+    }
+
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Backend for WriterBackend<W> {
+    fn size(&self) -> Result<Size> {
+        Ok(self.size.clone())
+    }
+
+    fn draw(&mut self, canvas: &TextCanvas) -> Result<()> {
+        write!(self.writer, "{MOVE_HOME}")?;
+
+        let lines = canvas.to_string();
+        for (i, line) in lines.split('\n').enumerate() {
+            if i > 0 { write!(self.writer, "\r\n")?; }
+
+            write!(self.writer, "{line}")?;
+        }
+
+        match canvas.cursor() {
+            Some((x, y)) => write!(self.writer, "\x1b[{};{}H{SHOW_CURSOR}", y + 1, x + 1)?,
+            None => write!(self.writer, "{HIDE_CURSOR}")?
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    fn poll_event(&mut self, timeout: Duration) -> Result<Option<BackendEvent>> {
+        std::thread::sleep(timeout);
+
+        Ok(None)
+    }
+
+    fn enter(&mut self, modes: &TerminalModes) -> Result<()> {
+        if modes.alternate_screen {
+            write!(self.writer, "{ENTER_ALTERNATE_SCREEN}")?;
+        }
+
+        if modes.bracketed_paste {
+            write!(self.writer, "{ENABLE_BRACKETED_PASTE}")?;
+        }
+
+        if modes.focus_events {
+            write!(self.writer, "{ENABLE_FOCUS_EVENTS}")?;
+        }
+
+        write!(self.writer, "{HIDE_CURSOR}{CLEAR_SCREEN}{MOVE_HOME}")?;
+        self.writer.flush()?;
+
+        self.modes = Some(modes.clone());
+
+        Ok(())
+    }
+
+    fn leave(&mut self) -> Result<()> {
+        let Some(modes) = self.modes.take() else { return Ok(()) };
+
+        write!(self.writer, "{SHOW_CURSOR}")?;
+
+        if modes.bracketed_paste {
+            write!(self.writer, "{DISABLE_BRACKETED_PASTE}")?;
+        }
+
+        if modes.focus_events {
+            write!(self.writer, "{DISABLE_FOCUS_EVENTS}")?;
+        }
+
+        if modes.alternate_screen {
+            write!(self.writer, "{LEAVE_ALTERNATE_SCREEN}")?;
+        }
+
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(backend: WriterBackend<Vec<u8>>) -> String {
+        String::from_utf8(backend.into_writer()).unwrap()
+    }
+
+    #[test]
+    fn frames_are_drawn_from_the_top_left_with_the_cursor_last() {
+        let mut canvas = TextCanvas::create(3, 2);
+        canvas.write("a", 0, 0);
+        canvas.write("b", 2, 1);
+        canvas.set_cursor(1, 1);
+
+        let mut backend = WriterBackend::new(vec![], 3, 2);
+        backend.draw(&canvas).unwrap();
+        backend.flush().unwrap();
+
+        assert_eq!(output(backend), format!("{MOVE_HOME}a  \r\n  b\x1b[2;2H{SHOW_CURSOR}"));
+    }
+
+    #[test]
+    fn leaving_undoes_the_modes_entered() {
+        let mut backend = WriterBackend::new(vec![], 3, 2);
+        let modes = TerminalModes { alternate_screen: true, bracketed_paste: false, focus_events: true };
+
+        backend.enter(&modes).unwrap();
+        backend.leave().unwrap();
+        // Only the first leave writes anything
+        backend.leave().unwrap();
+
+        assert_eq!(output(backend), format!(
+            "{ENTER_ALTERNATE_SCREEN}{ENABLE_FOCUS_EVENTS}{HIDE_CURSOR}{CLEAR_SCREEN}{MOVE_HOME}{SHOW_CURSOR}{DISABLE_FOCUS_EVENTS}{LEAVE_ALTERNATE_SCREEN}"
+        ));
+    }
+}
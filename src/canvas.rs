@@ -1,13 +1,16 @@
 use std::fmt::Display;
 
-use crate::{layout, rendering::DrawCommand};
+use crate::{layout, rendering::{state::RenderState, DrawCommand}};
 
 use layout::geometry::{Rect, Size};
 use unicode_segmentation::UnicodeSegmentation;
 
+#[derive(Clone)]
 pub struct TextCanvas {
     size: Size,
     contents: Vec<String>,
+    // Where the terminal cursor is shown, hidden when None
+    cursor: Option<(usize, usize)>
 }
 
 impl Default for TextCanvas {
@@ -21,6 +24,7 @@ impl TextCanvas {
         TextCanvas {
             size: Size::zero(),
             contents: Vec::new(),
+            cursor: None
         }
     }
 
@@ -28,6 +32,7 @@ impl TextCanvas {
         TextCanvas {
             size: size.clone(),
             contents: vec![" ".to_string(); size.width * size.height],
+            cursor: None
         }
     }
 
@@ -35,11 +40,16 @@ impl TextCanvas {
         TextCanvas {
             size: Size::new(width, height),
             contents: vec![" ".to_string(); width * height],
+            cursor: None
         }
     }
 }
 
 impl TextCanvas {
+    pub fn size(&self) -> &Size {
+        &self.size
+    }
+
     pub fn get_at(&self, x: usize, y: usize) -> Option<&str> {
         if x >= self.size.width || y >= self.size.height {
             return None;
@@ -87,37 +97,53 @@ impl TextCanvas {
 
     pub fn clear_with(&mut self, grapheme: &str) {
         self.draw_rect(&Rect::from_size(&self.size), grapheme);
+        self.cursor = None;
+    }
+
+    pub fn cursor(&self) -> Option<(usize, usize)> {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, x: usize, y: usize) {
+        if x >= self.size.width || y >= self.size.height { return; }
+
+        self.cursor = Some((x, y));
+    }
+
+    pub fn hide_cursor(&mut self) {
+        self.cursor = None;
     }
 }
 
 impl TextCanvas {
-    fn execute_draw_commands(&mut self, commands: &[DrawCommand]) {
+    pub(crate) fn execute_draw_commands(&mut self, commands: &[DrawCommand]) {
         for command in commands {
             match command {
                 DrawCommand:: Text(bounds, text) => {
                     let graphemes = text.as_str().graphemes(true)
                     .collect::<Vec<_>>();
                     
-                    let mut x = bounds.x as usize;
-                    let mut y = bounds.y as usize;
+                    // Bounds may start off-canvas (e.g. while sliding in), so positions are kept signed
+                    let mut x = bounds.x;
+                    let mut y = bounds.y;
 
                     let mut iter = graphemes.iter().peekable();
 
                     while let Some(g) = iter.next() {
                         if *g == "\n" {
                             y += 1;
-                            x = bounds.x as usize;
+                            x = bounds.x;
                             continue;
                         } else if *g == " " {
                             // don't write anything
-                        } else {
-                            self.write(g, x, y);
+                        } else if x >= 0 && y >= 0 {
+                            self.write(g, x as usize, y as usize);
                         }
 
                         x += 1;
-                        if (x - bounds.x as usize) >= bounds.width {
+                        if (x - bounds.x) as usize >= bounds.width {
                             y += 1;
-                            x = bounds.x as usize;
+                            x = bounds.x;
 
                             if let Some(next) = iter.peek() {
                                 if **next == "\n" {
@@ -130,6 +156,11 @@ impl TextCanvas {
                 DrawCommand::FillRect(bounds, grapheme) => {
                     self.draw_rect(bounds, grapheme);
                 }
+                DrawCommand::Cursor(x, y) => {
+                    if *x >= 0 && *y >= 0 {
+                        self.set_cursor(*x as usize, *y as usize);
+                    }
+                }
                 DrawCommand::StrokeRect(bounds, n, grapheme) => {
                     // Top
                     for x in bounds.x..(bounds.x + bounds.width as i64) {
@@ -180,44 +211,43 @@ impl TextCanvas {
     }
     
     pub fn render_layout<Ctx: Clone>(&mut self, layout: &layout::Layout<Ctx>, context: &mut Ctx) {
+        self.render_layout_with_state(layout, context, &mut RenderState::new());
+    }
+
+    // Renders keeping track of the layout across frames, which transitions and animations rely on
+    pub fn render_layout_with_state<Ctx: Clone>(&mut self, layout: &layout::Layout<Ctx>, context: &mut Ctx, state: &mut RenderState<Ctx>) {
         let self_bounds = Rect::sized(self.size.width, self.size.height);
-        let layout = layout.resolve_size(&self_bounds, context);
+        let layout = layout.resolve_size_with_state(&self_bounds, context, state);
         let bounds = layout.sizing.fit_into(&self_bounds);
 
-        let draw_commands = layout.resolve_draw_commands(&bounds, context);
+        let mut draw_commands = layout.resolve_draw_commands_with_state(&bounds, context, state);
+        draw_commands.extend(state.finish_frame());
 
         self.execute_draw_commands(&draw_commands);
     }
 
+    // Draws from wherever the cursor is, without clearing the screen
+    #[cfg(feature = "crossterm")]
     pub fn draw_on_buffer(&self) {
         use std::io::Write;
-        let chars = self.contents.clone();
         let mut stdout = std::io::stdout();
-        for n in 0..chars.len() {
-            let c = &chars[n];
-            let _ = crossterm::queue!(stdout, crossterm::style::Print(c.to_string()));
-    
-            if n < chars.len()-1 && (n + 1) % self.size.width == 0 {
-                let _ = crossterm::queue!(stdout, crossterm::cursor::MoveToNextLine(1) );
+
+        for n in 0..self.contents.len() {
+            let _ = crossterm::queue!(stdout, crossterm::style::Print(&self.contents[n]));
+
+            if n < self.contents.len()-1 && (n + 1) % self.size.width == 0 {
+                let _ = crossterm::queue!(stdout, crossterm::cursor::MoveToNextLine(1));
             }
         }
-    
+
         let _ = stdout.flush();
     }
     
     pub fn print(&self) {
         use std::io::Write;
-        let chars = self.contents.clone();
         let mut stdout = std::io::stdout();
-        for n in 0..chars.len() {
-            let c = &chars[n];
-            let _ = crossterm::queue!(stdout, crossterm::style::Print(c.to_string()));
-    
-            if n < chars.len()-1 && (n + 1) % self.size.width == 0 {
-                let _ = crossterm::queue!(stdout, crossterm::style::Print("\n".to_string()));
-            }
-        }
-    
+
+        let _ = write!(stdout, "{self}");
         let _ = stdout.flush();
     }
 }
@@ -0,0 +1,98 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    Bounce,
+    Spring
+}
+
+impl Easing {
+    // Maps linear progress in [0, 1] to eased progress. Bounce and Spring may overshoot
+    // in the middle but always land on 0 and 1.
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 { 2.0 * t * t } else { -1.0 + (4.0 - 2.0 * t) * t }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => {
+                let p = t - 1.0;
+                p * p * p + 1.0
+            }
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    let p = 2.0 * t - 2.0;
+                    0.5 * p * p * p + 1.0
+                }
+            }
+            Easing::Bounce => {
+                let n = 7.5625;
+                let d = 2.75;
+
+                if t < 1.0 / d {
+                    n * t * t
+                } else if t < 2.0 / d {
+                    let t = t - 1.5 / d;
+                    n * t * t + 0.75
+                } else if t < 2.5 / d {
+                    let t = t - 2.25 / d;
+                    n * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / d;
+                    n * t * t + 0.984375
+                }
+            }
+            Easing::Spring => {
+                if t >= 1.0 { return 1.0; }
+
+                // Under-damped oscillation settling on the target
+                1.0 - (-6.0 * t).exp() * (12.0 * t).cos()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 9] = [
+        Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut, Easing::CubicIn,
+        Easing::CubicOut, Easing::CubicInOut, Easing::Bounce, Easing::Spring
+    ];
+
+    #[test]
+    fn every_easing_starts_at_0_and_lands_on_1() {
+        for easing in ALL {
+            assert!(easing.apply(0.0).abs() < 1e-9, "{easing:?} starts at {}", easing.apply(0.0));
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-9, "{easing:?} ends at {}", easing.apply(1.0));
+        }
+    }
+
+    #[test]
+    fn progress_outside_of_0_to_1_is_clamped() {
+        for easing in ALL {
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0));
+            assert_eq!(easing.apply(2.0), easing.apply(1.0));
+        }
+    }
+
+    #[test]
+    fn symmetric_easings_are_halfway_at_the_middle() {
+        for easing in [Easing::Linear, Easing::EaseInOut, Easing::CubicInOut] {
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-9);
+        }
+    }
+}
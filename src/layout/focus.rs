@@ -0,0 +1,21 @@
+use crate::backend::keys::{KeyCode, KeyModifiers};
+
+use super::Layout;
+
+// Returns true when the key was handled, which keeps it from reaching the update
+pub type KeyHandler<Ctx> = fn(&mut Ctx, KeyCode, KeyModifiers) -> bool;
+
+pub type FocusedStyle<Ctx> = fn(Layout<Ctx>) -> Layout<Ctx>;
+
+#[derive(Clone)]
+pub struct Focusable<Ctx> {
+    pub id: String,
+    pub focused_style: Option<FocusedStyle<Ctx>>,
+    pub on_key: Option<KeyHandler<Ctx>>
+}
+
+impl<Ctx> Focusable<Ctx> {
+    pub fn new(id: &str) -> Self {
+        Focusable { id: id.to_string(), focused_style: None, on_key: None }
+    }
+}
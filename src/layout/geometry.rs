@@ -1,4 +1,4 @@
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Rect {
     pub x: i64,
     pub y: i64,
@@ -50,6 +50,22 @@ impl Rect {
     pub fn size(&self) -> Size {
         Size::new(self.width, self.height)
     }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        x >= self.x && x < self.max_x() && y >= self.y && y < self.max_y()
+    }
+
+    // The area both rects cover, if any
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let max_x = self.max_x().min(other.max_x());
+        let max_y = self.max_y().min(other.max_y());
+
+        if max_x <= x || max_y <= y { return None; }
+
+        Some(Rect::new(x, y, (max_x - x) as usize, (max_y - y) as usize))
+    }
 }
 
 impl Default for Rect {
@@ -88,7 +104,7 @@ impl Vector {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Size {
     pub width: usize,
     pub height: usize
@@ -119,7 +135,7 @@ pub struct Matrix<Item: Clone> {
 
 impl<Item: Clone> Matrix<Item> {
     pub fn with_rows(data: &[Item], row_count: usize) -> Self {
-        assert!(data.len() % row_count == 0, "Matrix must completely fill the grid");
+        assert!(data.len().is_multiple_of(row_count), "Matrix must completely fill the grid");
 
         let col_count = data.len() / row_count;
         Matrix { shape: (col_count, row_count), data: data.iter().map(|x| (*x).clone()).collect() }
@@ -148,4 +164,29 @@ impl<Item: Clone> Matrix<Item> {
 
         &mut self.data[index]
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_includes_the_origin_but_not_the_far_edges() {
+        let rect = Rect::new(-1, 2, 3, 2);
+
+        assert!(rect.contains(-1, 2));
+        assert!(rect.contains(1, 3));
+        assert!(!rect.contains(2, 3));
+        assert!(!rect.contains(0, 4));
+        assert!(!Rect::new(0, 0, 0, 1).contains(0, 0));
+    }
+
+    #[test]
+    fn intersections_cover_the_shared_area() {
+        let rect = Rect::new(0, 0, 4, 3);
+
+        assert_eq!(rect.intersection(&Rect::new(2, -1, 5, 2)), Some(Rect::new(2, 0, 2, 1)));
+        assert_eq!(rect.intersection(&Rect::new(1, 1, 1, 1)), Some(Rect::new(1, 1, 1, 1)));
+        assert_eq!(rect.intersection(&Rect::new(4, 0, 2, 2)), None);
+        assert_eq!(rect.intersection(&Rect::new(-3, -3, 2, 2)), None);
+    }
+}
@@ -1,11 +1,17 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, rc::Rc};
 
 pub mod sizing;
 pub mod alignment;
 pub mod geometry;
+pub mod transition;
+pub mod focus;
+
+use std::time::Duration;
 
 use geometry::Rect;
 
+use crate::{easing::Easing, rendering::state::RenderState, widgets::Widget};
+
 #[derive(Clone)]
 pub enum Layout<Ctx> {
     Text(String),
@@ -28,7 +34,13 @@ pub enum Layout<Ctx> {
     HorizontalStack(alignment::VerticalAlignment, usize, Vec<Layout<Ctx>>),
 
     DrawCanvas(fn(&mut Ctx, &Rect)->crate::canvas::TextCanvas),
-    WithContext(fn(&Ctx)->Layout<Ctx>)
+    WithContext(fn(&Ctx)->Layout<Ctx>),
+    Widget(Rc<dyn Widget<Ctx>>),
+
+    Identified(String, Box<Layout<Ctx>>),
+    Animation(Easing, Duration, Box<Layout<Ctx>>),
+    Transition(transition::Transition, Easing, Duration, Box<Layout<Ctx>>),
+    Focusable(focus::Focusable<Ctx>, Box<Layout<Ctx>>)
 }
 
 #[derive(Clone)]
@@ -52,7 +64,13 @@ pub enum SizedNode<Ctx: Clone> {
     VerticalStack(alignment::HorizontalAlignment, usize, Vec<SizedLayout<Ctx>>),
     HorizontalStack(alignment::VerticalAlignment, usize, Vec<SizedLayout<Ctx>>),
 
-    DrawCanvas(fn(&mut Ctx, &Rect)->crate::canvas::TextCanvas)
+    DrawCanvas(fn(&mut Ctx, &Rect)->crate::canvas::TextCanvas),
+    Widget(Rc<dyn Widget<Ctx>>),
+
+    Identified(String, SizedLayout<Ctx>),
+    Animation(Easing, Duration, SizedLayout<Ctx>),
+    Transition(transition::Transition, Easing, Duration, SizedLayout<Ctx>),
+    Focusable(String, Option<focus::KeyHandler<Ctx>>, SizedLayout<Ctx>)
 }
 
 #[derive(Clone)]
@@ -81,6 +99,10 @@ impl<Ctx: Clone> Layout<Ctx> {
     }
 
     pub fn resolve_size(&self, bounds: &Rect, context: &mut Ctx) -> SizedLayout<Ctx> {
+        self.resolve_size_with_state(bounds, context, &mut RenderState::new())
+    }
+
+    pub fn resolve_size_with_state(&self, bounds: &Rect, context: &mut Ctx, state: &mut RenderState<Ctx>) -> SizedLayout<Ctx> {
         use Layout::*;
         use sizing::Sizing::*;
 
@@ -105,7 +127,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                 SizedLayout::new(SizedNode::Text(t.clone()), sizing)
             }
             VCenter(node) => {
-                let resolved = node.resolve_size(bounds, context);
+                let resolved = node.resolve_size_with_state(bounds, context, state);
                 let content_size = resolved.sizing.clone();
 
                 let min_height = content_size.vertical.min_content_size();
@@ -115,7 +137,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                 SizedLayout::new(SizedNode::VCenter(resolved), sizing)
             }
             VBottomAlign(node) => {
-                let resolved = node.resolve_size(bounds, context);
+                let resolved = node.resolve_size_with_state(bounds, context, state);
                 let content_size = resolved.sizing.clone();
 
                 let min_height = content_size.vertical.min_content_size();
@@ -125,7 +147,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                 SizedLayout::new(SizedNode::VBottomAlign(resolved), sizing)
             }
             HCenter(node) => {
-                let resolved = node.resolve_size(bounds, context);
+                let resolved = node.resolve_size_with_state(bounds, context, state);
                 let content_size = resolved.sizing.clone();
 
                 let min_width = content_size.horizontal.min_content_size();
@@ -135,7 +157,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                 SizedLayout::new(SizedNode::HCenter(resolved), sizing)
             }
             HRightAlign(node) => {
-                let resolved = node.resolve_size(bounds, context);
+                let resolved = node.resolve_size_with_state(bounds, context, state);
                 let content_size = resolved.sizing.clone();
 
                 let min_width = content_size.horizontal.min_content_size();
@@ -145,7 +167,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                 SizedLayout::new(SizedNode::HRightAlign(resolved), sizing)
             }
             VTopAlign(node) => {
-                let resolved = node.resolve_size(bounds, context);
+                let resolved = node.resolve_size_with_state(bounds, context, state);
                 let content_size = resolved.sizing.clone();
 
                 let min_height = content_size.vertical.min_content_size();
@@ -155,7 +177,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                 SizedLayout::new(SizedNode::VTopAlign(resolved), sizing)
             }
             HLeftAlign(node) => {
-                let resolved = node.resolve_size(bounds, context);
+                let resolved = node.resolve_size_with_state(bounds, context, state);
                 let content_size = resolved.sizing.clone();
 
                 let min_width = content_size.horizontal.min_content_size();
@@ -168,7 +190,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                 let mut bounds = bounds.clone();
                 bounds.width = *size;
 
-                let resolved_content = node.resolve_size(&bounds, context);
+                let resolved_content = node.resolve_size_with_state(&bounds, context, state);
                 let mut frame = resolved_content.sizing.clone();
                 frame.horizontal = Static(*size);
 
@@ -178,14 +200,14 @@ impl<Ctx: Clone> Layout<Ctx> {
                 let mut bounds = bounds.clone();
                 bounds.height = *size;
 
-                let resolved_content = node.resolve_size(&bounds, context);
+                let resolved_content = node.resolve_size_with_state(&bounds, context, state);
                 let mut frame = resolved_content.sizing.clone();
                 frame.vertical = Static(*size);
 
                 SizedLayout::new(SizedNode::Height(*size, resolved_content), frame)
             }
             TopPadding(n, node) | BottomPadding(n, node) => {
-                let resolved = node.resolve_size(bounds, context);
+                let resolved = node.resolve_size_with_state(bounds, context, state);
                 let mut frame = resolved.sizing.clone();
                 
                 frame.vertical.clamped_add(*n);
@@ -203,7 +225,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                     let mut bounds = bounds.clone();
                     bounds.height = bounds.height.saturating_sub(*n);
 
-                    let resolved_content = node.resolve_size(&bounds, context);
+                    let resolved_content = node.resolve_size_with_state(&bounds, context, state);
                     let mut frame = resolved_content.sizing.clone();
 
                     frame.vertical.clamped_add(*n);
@@ -214,7 +236,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                 }
             }
             LeftPadding(n, node) | RightPadding(n, node) => {
-                let resolved = node.resolve_size(bounds, context);
+                let resolved = node.resolve_size_with_state(bounds, context, state);
                 let mut frame = resolved.sizing.clone();
 
                 let make_node = |n: usize, node: SizedLayout<Ctx>|{
@@ -231,7 +253,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                     let mut bounds = bounds.clone();
                     bounds.width = bounds.width.saturating_sub(*n);
 
-                    let resolved_content = node.resolve_size(&bounds, context);
+                    let resolved_content = node.resolve_size_with_state(&bounds, context, state);
                     frame = resolved_content.sizing.clone();
                     frame.horizontal.clamped_add(*n);
 
@@ -243,14 +265,14 @@ impl<Ctx: Clone> Layout<Ctx> {
                 }
             }
             Background(c, node) => {
-                let resolved_content = node.resolve_size(bounds, context);
+                let resolved_content = node.resolve_size_with_state(bounds, context, state);
                 let frame = resolved_content.sizing.clone();
 
                 SizedLayout::new(SizedNode::Background(*c, resolved_content), frame)
             }
             Border(n, c, edges, node) => {
                 let outer_bounds = bounds;
-                let mut resolved_content = node.resolve_size(outer_bounds, context);
+                let mut resolved_content = node.resolve_size_with_state(outer_bounds, context, state);
                 let mut frame = resolved_content.sizing.clone();
 
                 let mut added_height = 0;
@@ -269,7 +291,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                     let mut bounds = outer_bounds.clone();
                     bounds.height = bounds.height.saturating_sub(added_height);
 
-                    resolved_content = node.resolve_size(&bounds, context);
+                    resolved_content = node.resolve_size_with_state(&bounds, context, state);
                     frame = resolved_content.sizing.clone();
 
                     frame.vertical.clamped_add(added_height);
@@ -291,7 +313,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                     let mut bounds = outer_bounds.clone();
                     bounds.width = bounds.width.saturating_sub(added_width);
 
-                    resolved_content = node.resolve_size(&bounds, context);
+                    resolved_content = node.resolve_size_with_state(&bounds, context, state);
                     frame = resolved_content.sizing.clone();
 
                     frame.horizontal.clamped_add(added_width);
@@ -308,7 +330,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                 let mut resolved_children: Vec<SizedLayout<_>> = vec![];
 
                 for node in nodes {
-                    let resolved_node = node.resolve_size(&bounds, context);
+                    let resolved_node = node.resolve_size_with_state(&bounds, context, state);
                     let node_sizing = resolved_node.sizing.clone();
                     result.horizontal = match result.horizontal {
                         Static(j) => match node_sizing.horizontal {
@@ -335,7 +357,7 @@ impl<Ctx: Clone> Layout<Ctx> {
                 let mut resolved_children = vec![];
 
                 for node in nodes {
-                    let resolved_node = node.resolve_size(&bounds, context);
+                    let resolved_node = node.resolve_size_with_state(&bounds, context, state);
                     let node_sizing = resolved_node.sizing.clone();
                     result.vertical = match result.vertical {
                         Static(j) => match node_sizing.vertical {
@@ -364,10 +386,44 @@ impl<Ctx: Clone> Layout<Ctx> {
                     )
                 )
             },
+            Widget(widget) => {
+                let sizing = widget.sizing(bounds, context);
+
+                SizedLayout::new(SizedNode::Widget(widget.clone()), sizing)
+            }
             WithContext(node) => {
                 let node = node(context);
 
-                node.resolve_size(bounds, context)
+                node.resolve_size_with_state(bounds, context, state)
+            }
+            Identified(id, node) => {
+                let resolved = node.resolve_size_with_state(bounds, context, state);
+                let frame = resolved.sizing.clone();
+
+                SizedLayout::new(SizedNode::Identified(id.clone(), resolved), frame)
+            }
+            Animation(easing, duration, node) => {
+                let resolved = node.resolve_size_with_state(bounds, context, state);
+                let frame = resolved.sizing.clone();
+
+                SizedLayout::new(SizedNode::Animation(*easing, *duration, resolved), frame)
+            }
+            Transition(kind, easing, duration, node) => {
+                let resolved = node.resolve_size_with_state(bounds, context, state);
+                let frame = resolved.sizing.clone();
+
+                SizedLayout::new(SizedNode::Transition(kind.clone(), *easing, *duration, resolved), frame)
+            }
+            Focusable(focusable, node) => {
+                let resolved = match focusable.focused_style {
+                    Some(style) if state.is_focused(&focusable.id) => {
+                        style(node.as_ref().clone()).resolve_size_with_state(bounds, context, state)
+                    }
+                    _ => node.resolve_size_with_state(bounds, context, state)
+                };
+                let frame = resolved.sizing.clone();
+
+                SizedLayout::new(SizedNode::Focusable(focusable.id.clone(), focusable.on_key, resolved), frame)
             }
         }
     }
@@ -378,6 +434,10 @@ impl<Ctx: Clone> Layout<Ctx> {
         Layout::Text(content.to_string())
     }
 
+    pub fn widget(widget: impl Widget<Ctx> + 'static) -> Layout<Ctx> {
+        Layout::Widget(Rc::new(widget))
+    }
+
     pub fn center(self) -> Layout<Ctx> {
         Layout::VCenter(Box::new(Layout::HCenter(Box::new(self))))
     }
@@ -454,6 +514,62 @@ impl<Ctx: Clone> Layout<Ctx> {
         Layout::Background(c, Box::new(self))
     }
 
+    pub fn id(self, id: &str) -> Layout<Ctx> {
+        Layout::Identified(id.to_string(), Box::new(self))
+    }
+
+    pub fn animation(self, easing: Easing, duration: Duration) -> Layout<Ctx> {
+        Layout::Animation(easing, duration, Box::new(self))
+    }
+
+    pub fn transition(self, kind: transition::Transition) -> Layout<Ctx> {
+        Layout::Transition(kind, transition::DEFAULT_TRANSITION_EASING, transition::DEFAULT_TRANSITION_DURATION, Box::new(self))
+    }
+
+    pub fn transition_with(self, kind: transition::Transition, easing: Easing, duration: Duration) -> Layout<Ctx> {
+        Layout::Transition(kind, easing, duration, Box::new(self))
+    }
+
+    pub fn focusable(self, id: &str) -> Layout<Ctx> {
+        Layout::Focusable(focus::Focusable::new(id), Box::new(self))
+    }
+
+    // Lays the node out through `style` while it has focus. Goes to the closest `focusable()`
+    // this was called on, even with other modifiers in between, and does nothing without one.
+    pub fn focused_style(mut self, style: focus::FocusedStyle<Ctx>) -> Layout<Ctx> {
+        if let Some(focusable) = self.enclosing_focusable() {
+            focusable.focused_style = Some(style);
+        }
+
+        self
+    }
+
+    // Sees key events before anything else while the node has focus. Goes to the closest
+    // `focusable()` like `focused_style` does.
+    pub fn on_key(mut self, handler: focus::KeyHandler<Ctx>) -> Layout<Ctx> {
+        if let Some(focusable) = self.enclosing_focusable() {
+            focusable.on_key = Some(handler);
+        }
+
+        self
+    }
+
+    // The first focusable node going down through modifiers, stopping at anything with
+    // more or less than one child
+    fn enclosing_focusable(&mut self) -> Option<&mut focus::Focusable<Ctx>> {
+        use Layout::*;
+
+        match self {
+            Focusable(focusable, _) => Some(focusable),
+            Width(_, node) | Height(_, node)
+            | TopPadding(_, node) | RightPadding(_, node) | BottomPadding(_, node) | LeftPadding(_, node)
+            | VCenter(node) | HCenter(node) | VBottomAlign(node) | HRightAlign(node) | VTopAlign(node) | HLeftAlign(node)
+            | Background(_, node) | Border(_, _, _, node)
+            | Identified(_, node) | Animation(_, _, node) | Transition(_, _, _, node) => node.enclosing_focusable(),
+            Text(_) | VerticalStack(..) | HorizontalStack(..) | DrawCanvas(_) | WithContext(_) | Widget(_) => None
+        }
+    }
+
     pub fn vertical_stack(nodes: Vec<Layout<Ctx>>) -> Layout<Ctx> {
         Layout::VerticalStack(alignment::HorizontalAlignment::Center, 0, nodes)
     }
@@ -482,4 +598,38 @@ impl<Ctx: Clone> Layout<Ctx> {
 
         Layout::VerticalStack(alignment::HorizontalAlignment::Center, spacing, rows)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignore_keys(_: &mut (), _: crate::backend::keys::KeyCode, _: crate::backend::keys::KeyModifiers) -> bool {
+        false
+    }
+
+    fn focusable(layout: &mut Layout<()>) -> Option<&mut focus::Focusable<()>> {
+        layout.enclosing_focusable()
+    }
+
+    #[test]
+    fn focus_modifiers_reach_the_focusable_through_other_modifiers() {
+        let mut layout: Layout<()> = Layout::text("x").focusable("x").padding(1).center()
+            .focused_style(|layout| layout)
+            .on_key(ignore_keys);
+
+        let focusable = focusable(&mut layout).unwrap();
+        assert_eq!(focusable.id, "x");
+        assert!(focusable.focused_style.is_some());
+        assert!(focusable.on_key.is_some());
+    }
+
+    #[test]
+    fn focus_modifiers_without_a_focusable_do_nothing() {
+        let mut layout: Layout<()> = Layout::text("x").padding(1).on_key(ignore_keys).focused_style(|layout| layout);
+        assert!(focusable(&mut layout).is_none());
+
+        let mut stacked: Layout<()> = Layout::horizontal_stack(vec![Layout::text("x").focusable("x")]).on_key(ignore_keys);
+        assert!(focusable(&mut stacked).is_none());
+    }
+}
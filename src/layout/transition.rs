@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+use crate::easing::Easing;
+
+use super::alignment::Edge;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Transition {
+    // Moves in from (and out towards) the given edge, clipped to the node's frame
+    Slide(Edge),
+    // Uncovers the node from left to right
+    Reveal
+}
+
+pub const DEFAULT_TRANSITION_EASING: Easing = Easing::EaseInOut;
+pub const DEFAULT_TRANSITION_DURATION: Duration = Duration::from_millis(250);
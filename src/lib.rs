@@ -10,3 +10,7 @@ pub mod layout;
 pub mod canvas;
 pub mod animation;
 pub mod rendering;
+pub mod backend;
+pub mod recording;
+pub mod widgets;
+pub mod easing;
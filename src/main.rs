@@ -7,7 +7,8 @@ fn main() -> Result<()> {
     canvas.set_update(app_update);
 
     let config = AnimationRunConfig{
-        buffer_type: AnimationBuffer::Alternate
+        buffer_type: AnimationBuffer::Alternate,
+        ..Default::default()
     };
 
     canvas.run(config)?;
@@ -16,7 +17,7 @@ fn main() -> Result<()> {
 }
 
 fn app_update(ctx: &mut PlainAnimationContext) {
-    while let Some(event) = ctx.pending_events.pop() {
+    for event in ctx.take_events() {
         if let AnimationEvent::KeyEvent(e, modifiers) = event {
             if modifiers.contains(KeyModifiers::CONTROL) {
                 if let KeyCode::Char(' ') = e {
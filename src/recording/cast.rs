@@ -0,0 +1,346 @@
+use std::{io::Write, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{canvas::TextCanvas, layout::geometry::Size};
+
+use super::json::{self, JsonValue};
+
+// Writes frames in the asciinema v2 format: a JSON header line followed by one
+// `[time, code, data]` line per event.
+pub struct CastWriter<W: Write> {
+    writer: W,
+    last_frame: Option<String>
+}
+
+impl<W: Write> CastWriter<W> {
+    pub fn new(mut writer: W, size: &Size) -> Result<Self> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        writeln!(
+            writer,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {timestamp}, \"env\": {{\"TERM\": \"xterm-256color\"}}}}",
+            size.width,
+            size.height
+        )?;
+
+        Ok(CastWriter { writer, last_frame: None })
+    }
+
+    // Frames identical to the previous one are skipped, the player just keeps showing it.
+    pub fn write_frame(&mut self, time_secs: f64, canvas: &TextCanvas) -> Result<()> {
+        let frame = frame_output(canvas);
+
+        if self.last_frame.as_ref() == Some(&frame) { return Ok(()); }
+
+        self.write_event(time_secs, "o", &frame)?;
+        self.last_frame = Some(frame);
+
+        Ok(())
+    }
+
+    pub fn write_resize(&mut self, time_secs: f64, size: &Size) -> Result<()> {
+        self.last_frame = None;
+
+        self.write_event(time_secs, "r", &format!("{}x{}", size.width, size.height))
+    }
+
+    fn write_event(&mut self, time_secs: f64, code: &str, data: &str) -> Result<()> {
+        writeln!(self.writer, "[{time_secs:.6}, {}, {}]", json::escape(code), json::escape(data))?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+fn frame_output(canvas: &TextCanvas) -> String {
+    // Home the cursor and repaint everything, so every event is a complete frame on its own
+    format!("\x1b[H{}", canvas.to_string().replace('\n', "\r\n"))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CastEvent {
+    Output(String),
+    Resize(usize, usize),
+    Other(String, String)
+}
+
+pub struct Cast {
+    pub width: usize,
+    pub height: usize,
+    pub events: Vec<(f64, CastEvent)>
+}
+
+pub struct CastFrame {
+    pub time_secs: f64,
+    pub canvas: TextCanvas
+}
+
+impl Cast {
+    pub fn load(path: impl AsRef<Path>) -> Result<Cast> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read cast file {}", path.display()))?;
+
+        Cast::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Cast> {
+        let mut lines = contents.lines().filter(|l| !l.trim().is_empty());
+
+        let header = json::parse(lines.next().ok_or_else(|| anyhow!("Cast is empty"))?)?;
+
+        if header.get("version").and_then(JsonValue::as_f64) != Some(2.0) {
+            return Err(anyhow!("Only asciinema v2 casts are supported"));
+        }
+
+        let dimension = |key: &str| header.get(key)
+            .and_then(JsonValue::as_f64)
+            .map(|n| n as usize)
+            .ok_or_else(|| anyhow!("Cast header is missing '{key}'"));
+
+        let width = dimension("width")?;
+        let height = dimension("height")?;
+
+        let mut events = vec![];
+        for (i, line) in lines.enumerate() {
+            let event = json::parse(line)?;
+
+            let (time, code, data) = match event.as_array() {
+                Some([time, code, data]) => (time.as_f64(), code.as_str(), data.as_str()),
+                _ => (None, None, None)
+            };
+
+            let (Some(time), Some(code), Some(data)) = (time, code, data) else {
+                return Err(anyhow!("Malformed event on line {}", i + 2));
+            };
+
+            let event = match code {
+                "o" => CastEvent::Output(data.to_string()),
+                "r" => {
+                    let (columns, rows) = data.split_once('x')
+                        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                        .ok_or_else(|| anyhow!("Malformed resize event on line {}", i + 2))?;
+
+                    CastEvent::Resize(columns, rows)
+                }
+                _ => CastEvent::Other(code.to_string(), data.to_string())
+            };
+
+            events.push((time, event));
+        }
+
+        Ok(Cast { width, height, events })
+    }
+
+    // Plays the output back through a small terminal emulator, taking a snapshot after every output event.
+    pub fn frames(&self) -> Vec<CastFrame> {
+        let mut screen = Screen::new(self.width, self.height);
+        let mut frames = vec![];
+
+        for (time, event) in &self.events {
+            match event {
+                CastEvent::Output(data) => {
+                    screen.feed(data);
+                    frames.push(CastFrame { time_secs: *time, canvas: screen.canvas.clone() });
+                }
+                CastEvent::Resize(columns, rows) => {
+                    screen = Screen::new(*columns, *rows);
+                }
+                CastEvent::Other(_, _) => {}
+            }
+        }
+
+        frames
+    }
+}
+
+struct Screen {
+    canvas: TextCanvas,
+    x: usize,
+    y: usize
+}
+
+impl Screen {
+    fn new(width: usize, height: usize) -> Self {
+        Screen { canvas: TextCanvas::create(width, height), x: 0, y: 0 }
+    }
+
+    fn feed(&mut self, data: &str) {
+        let graphemes = data.graphemes(true).collect::<Vec<_>>();
+        let mut i = 0;
+
+        while i < graphemes.len() {
+            match graphemes[i] {
+                "\x1b" => {
+                    i = self.escape_sequence(&graphemes, i + 1);
+                    continue;
+                }
+                "\r" => self.x = 0,
+                "\n" | "\r\n" => {
+                    if graphemes[i] == "\r\n" { self.x = 0; }
+                    self.y += 1;
+                }
+                g => {
+                    let size = self.canvas.size().clone();
+                    if self.x >= size.width {
+                        self.x = 0;
+                        self.y += 1;
+                    }
+
+                    self.canvas.write(g, self.x, self.y);
+                    self.x += 1;
+                }
+            }
+
+            i += 1;
+        }
+    }
+
+    // Applies the sequence starting after the escape character and returns where it ends.
+    // Only cursor positioning and clearing matter for snapshots, everything else is skipped.
+    fn escape_sequence(&mut self, graphemes: &[&str], start: usize) -> usize {
+        if graphemes.get(start) != Some(&"[") { return start + 1; }
+
+        let mut end = start + 1;
+        let mut parameters = String::new();
+
+        while let Some(g) = graphemes.get(end) {
+            let c = g.chars().next().unwrap_or('\0');
+            if ('\u{40}'..='\u{7e}').contains(&c) { break; }
+
+            parameters.push_str(g);
+            end += 1;
+        }
+
+        let Some(command) = graphemes.get(end) else { return end; };
+
+        let numbers: Vec<usize> = parameters.split(';').map(|n| n.parse().unwrap_or(0)).collect();
+        let number = |index: usize, default: usize| numbers.get(index).copied().filter(|n| *n > 0).unwrap_or(default);
+
+        match *command {
+            "H" | "f" => {
+                self.y = number(0, 1) - 1;
+                self.x = number(1, 1) - 1;
+            }
+            "A" => self.y = self.y.saturating_sub(number(0, 1)),
+            "B" => self.y += number(0, 1),
+            "C" => self.x += number(0, 1),
+            "D" => self.x = self.x.saturating_sub(number(0, 1)),
+            "E" => {
+                self.y += number(0, 1);
+                self.x = 0;
+            }
+            "J" if parameters == "2" || parameters == "3" => {
+                self.canvas.clear_with(" ");
+            }
+            _ => {}
+        }
+
+        end + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canvas(rows: &[&str]) -> TextCanvas {
+        let mut canvas = TextCanvas::create(rows[0].chars().count(), rows.len());
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, grapheme) in row.graphemes(true).enumerate() {
+                canvas.write(grapheme, x, y);
+            }
+        }
+
+        canvas
+    }
+
+    fn written(write: impl FnOnce(&mut CastWriter<&mut Vec<u8>>)) -> String {
+        let mut output = vec![];
+        let mut writer = CastWriter::new(&mut output, &Size::new(3, 2)).unwrap();
+        write(&mut writer);
+        writer.flush().unwrap();
+        drop(writer);
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn written_frames_play_back_the_same() {
+        let first = canvas(&["abc", "d f"]);
+        let second = canvas(&["\"\\ ", "é✓ "]);
+
+        let contents = written(|writer| {
+            writer.write_frame(0.0, &first).unwrap();
+            writer.write_frame(0.5, &second).unwrap();
+        });
+
+        let cast = Cast::parse(&contents).unwrap();
+        assert_eq!((cast.width, cast.height), (3, 2));
+
+        let frames = cast.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].canvas.to_string(), first.to_string());
+        assert_eq!(frames[1].time_secs, 0.5);
+        assert_eq!(frames[1].canvas.to_string(), second.to_string());
+    }
+
+    #[test]
+    fn repeated_frames_are_only_written_once() {
+        let frame = canvas(&["abc", "def"]);
+
+        let contents = written(|writer| {
+            writer.write_frame(0.0, &frame).unwrap();
+            writer.write_frame(0.1, &frame).unwrap();
+        });
+
+        assert_eq!(Cast::parse(&contents).unwrap().events.len(), 1);
+    }
+
+    #[test]
+    fn resizes_play_back_at_the_new_size() {
+        let frame = canvas(&["abc", "def"]);
+        let resized = canvas(&["ab", "cd", "ef"]);
+
+        let contents = written(|writer| {
+            writer.write_frame(0.0, &frame).unwrap();
+            writer.write_resize(1.0, &Size::new(2, 3)).unwrap();
+            writer.write_frame(1.0, &frame).unwrap();
+            writer.write_frame(1.0, &resized).unwrap();
+        });
+
+        let cast = Cast::parse(&contents).unwrap();
+        assert_eq!(cast.events[1], (1.0, CastEvent::Resize(2, 3)));
+
+        // The frame after a resize is written even when it didn't change
+        let frames = cast.frames();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[2].canvas.to_string(), resized.to_string());
+    }
+
+    #[test]
+    fn malformed_casts_are_errors() {
+        let header = "{\"version\": 2, \"width\": 3, \"height\": 2}";
+
+        assert!(Cast::parse("").is_err());
+        assert!(Cast::parse("{\"version\": 1, \"width\": 3, \"height\": 2}").is_err());
+        assert!(Cast::parse("{\"version\": 2, \"width\": 3}").is_err());
+        assert!(Cast::parse(&format!("{header}\n[0.5, \"o\"]")).is_err());
+        assert!(Cast::parse(&format!("{header}\n[0.5, \"r\", \"3by2\"]")).is_err());
+        assert!(Cast::parse(&format!("{header}\n[0.5, \"o\", \"x\"")).is_err());
+
+        let cast = Cast::parse(&format!("{header}\n\n[0.5, \"i\", \"x\"]\n")).unwrap();
+        assert_eq!(cast.events, vec![(0.5, CastEvent::Other("i".to_string(), "x".to_string()))]);
+    }
+}
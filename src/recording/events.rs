@@ -0,0 +1,277 @@
+use std::{io::Write, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::{backend::{keys::{KeyCode, KeyModifiers}, BackendEvent}, layout::geometry::Size};
+
+use super::json;
+
+const HEADER: &str = "# textyle event log v1";
+
+// Line based log of the input the animation loop saw.
+//
+//     S <columns> <rows>         size the run started at, right after the header
+//     F <delta_milis>            starts a frame
+//     K <key> <modifier bits>    key pressed during the last frame
+//     R <columns> <rows>         terminal resized during the last frame
+//     P <json string>            text pasted during the last frame
+//     G / L                      focus gained / lost during the last frame
+//     M <json string>            message received at the start of the last frame
+//
+// Messages sent through an `EventSender` are only logged when the animation has a `MessageCodec`.
+pub struct EventLogWriter<W: Write> {
+    writer: W
+}
+
+impl<W: Write> EventLogWriter<W> {
+    pub fn new(mut writer: W, size: &Size) -> Result<Self> {
+        writeln!(writer, "{HEADER}")?;
+        writeln!(writer, "S {} {}", size.width, size.height)?;
+
+        Ok(EventLogWriter { writer })
+    }
+
+    pub fn write_frame(&mut self, delta_milis: f64) -> Result<()> {
+        // `{:?}` keeps every digit, so the replay gets the exact same value back
+        writeln!(self.writer, "F {delta_milis:?}")?;
+
+        Ok(())
+    }
+
+    pub fn write_event(&mut self, event: &BackendEvent) -> Result<()> {
+        match event {
+            BackendEvent::Key(code, modifiers) => writeln!(self.writer, "K {} {}", key_name(code), modifiers.bits())?,
+            BackendEvent::Resize(columns, rows) => writeln!(self.writer, "R {columns} {rows}")?,
+            BackendEvent::Paste(text) => writeln!(self.writer, "P {}", json::escape(text))?,
+            BackendEvent::FocusGained => writeln!(self.writer, "G")?,
+            BackendEvent::FocusLost => writeln!(self.writer, "L")?,
+            // Job control only concerns the terminal the log was written in
+            BackendEvent::Suspend | BackendEvent::Resume => {}
+        }
+
+        Ok(())
+    }
+
+    // Takes a message already encoded by the animation's `MessageCodec`
+    pub fn write_message(&mut self, message: &str) -> Result<()> {
+        writeln!(self.writer, "M {}", json::escape(message))?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggedFrame {
+    pub delta_milis: f64,
+    pub events: Vec<BackendEvent>,
+    // Still encoded
+    pub messages: Vec<String>
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventLog {
+    // Replays start at this size instead of the terminal's, logs from before it was recorded don't have it
+    pub size: Option<Size>,
+    pub frames: Vec<LoggedFrame>
+}
+
+impl EventLog {
+    pub fn load(path: impl AsRef<Path>) -> Result<EventLog> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read event log {}", path.display()))?;
+
+        EventLog::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<EventLog> {
+        let mut size = None;
+        let mut frames: Vec<LoggedFrame> = vec![];
+
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+
+            let parts: Vec<_> = line.split_whitespace().collect();
+            let malformed = || anyhow!("Malformed event log entry on line {}: '{line}'", i + 1);
+
+            if let Some(text) = line.strip_prefix("P ") {
+                let text = json::parse(text)?.as_str().ok_or_else(malformed)?.to_string();
+
+                let frame = frames.last_mut().ok_or_else(malformed)?;
+                frame.events.push(BackendEvent::Paste(text));
+                continue;
+            }
+
+            if let Some(message) = line.strip_prefix("M ") {
+                let message = json::parse(message)?.as_str().ok_or_else(malformed)?.to_string();
+
+                let frame = frames.last_mut().ok_or_else(malformed)?;
+                frame.messages.push(message);
+                continue;
+            }
+
+            match parts.as_slice() {
+                ["S", columns, rows] if frames.is_empty() && size.is_none() => {
+                    let columns = columns.parse().map_err(|_| malformed())?;
+                    let rows = rows.parse().map_err(|_| malformed())?;
+
+                    size = Some(Size::new(columns, rows));
+                }
+                ["F", delta] => {
+                    let delta_milis = delta.parse().map_err(|_| malformed())?;
+                    frames.push(LoggedFrame { delta_milis, events: vec![], messages: vec![] });
+                }
+                ["K", key, modifiers] => {
+                    let code = parse_key_name(key).ok_or_else(malformed)?;
+                    let modifiers = KeyModifiers::from_bits(modifiers.parse().map_err(|_| malformed())?);
+
+                    let frame = frames.last_mut().ok_or_else(malformed)?;
+                    frame.events.push(BackendEvent::Key(code, modifiers));
+                }
+                ["R", columns, rows] => {
+                    let columns = columns.parse().map_err(|_| malformed())?;
+                    let rows = rows.parse().map_err(|_| malformed())?;
+
+                    let frame = frames.last_mut().ok_or_else(malformed)?;
+                    frame.events.push(BackendEvent::Resize(columns, rows));
+                }
+                ["G"] | ["L"] => {
+                    let event = if parts[0] == "G" { BackendEvent::FocusGained } else { BackendEvent::FocusLost };
+
+                    let frame = frames.last_mut().ok_or_else(malformed)?;
+                    frame.events.push(event);
+                }
+                _ => bail!(malformed())
+            }
+        }
+
+        Ok(EventLog { size, frames })
+    }
+}
+
+fn key_name(code: &KeyCode) -> String {
+    match code {
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::BackTab => "backtab".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Insert => "insert".to_string(),
+        KeyCode::F(n) => format!("f{n}"),
+        // Stored as a code point so spaces and other separators survive
+        KeyCode::Char(c) => format!("char:{}", *c as u32),
+        KeyCode::Null => "null".to_string(),
+        KeyCode::Esc => "esc".to_string()
+    }
+}
+
+fn parse_key_name(name: &str) -> Option<KeyCode> {
+    let code = match name {
+        "backspace" => KeyCode::Backspace,
+        "enter" => KeyCode::Enter,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "null" => KeyCode::Null,
+        "esc" => KeyCode::Esc,
+        name => {
+            if let Some(code_point) = name.strip_prefix("char:") {
+                KeyCode::Char(char::from_u32(code_point.parse().ok()?)?)
+            } else if let Some(n) = name.strip_prefix('f') {
+                KeyCode::F(n.parse().ok()?)
+            } else {
+                return None;
+            }
+        }
+    };
+
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(write: impl FnOnce(&mut EventLogWriter<&mut Vec<u8>>)) -> String {
+        let mut output = vec![];
+        let mut writer = EventLogWriter::new(&mut output, &Size::new(80, 24)).unwrap();
+        write(&mut writer);
+        writer.flush().unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn logged_frames_parse_back_the_same() {
+        let events = vec![
+            BackendEvent::Key(KeyCode::Char(' '), KeyModifiers::NONE),
+            BackendEvent::Key(KeyCode::Char('é'), KeyModifiers::SHIFT | KeyModifiers::ALT),
+            BackendEvent::Resize(120, 40),
+            BackendEvent::Paste("two\nlines with \"quotes\" ✓".to_string()),
+            BackendEvent::FocusLost,
+            BackendEvent::FocusGained
+        ];
+
+        let contents = written(|log| {
+            log.write_frame(16.666666666666668).unwrap();
+            log.write_message("{\"loaded\": 3}").unwrap();
+            events.iter().for_each(|e| log.write_event(e).unwrap());
+            log.write_frame(0.1).unwrap();
+        });
+
+        let log = EventLog::parse(&contents).unwrap();
+
+        assert_eq!(log.size, Some(Size::new(80, 24)));
+        assert_eq!(log.frames, vec![
+            LoggedFrame { delta_milis: 16.666666666666668, events, messages: vec!["{\"loaded\": 3}".to_string()] },
+            LoggedFrame { delta_milis: 0.1, events: vec![], messages: vec![] }
+        ]);
+    }
+
+    #[test]
+    fn every_key_name_parses_back() {
+        let keys = [
+            KeyCode::Backspace, KeyCode::Enter, KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down,
+            KeyCode::Home, KeyCode::End, KeyCode::PageUp, KeyCode::PageDown, KeyCode::Tab, KeyCode::BackTab,
+            KeyCode::Delete, KeyCode::Insert, KeyCode::F(1), KeyCode::F(12), KeyCode::Char('f'),
+            KeyCode::Char('\t'), KeyCode::Null, KeyCode::Esc
+        ];
+
+        for key in keys {
+            assert_eq!(parse_key_name(&key_name(&key)), Some(key), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn malformed_logs_are_errors() {
+        for contents in ["K char:97 0", "F fast", "F 1\nK nope 0", "F 1\nK char:97", "F 1\nR 10", "F 1\nP unquoted", "F 1\nM 3", "F 1\nX", "S 80", "F 1\nS 80 24", "S 80 24\nS 80 24"] {
+            assert!(EventLog::parse(contents).is_err(), "{contents:?} parsed");
+        }
+
+        assert_eq!(EventLog::parse("# comment\n\n").unwrap(), EventLog::default());
+    }
+}
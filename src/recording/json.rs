@@ -0,0 +1,306 @@
+// Just enough JSON to read asciinema casts back.
+
+use anyhow::{anyhow, bail, Result};
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>)
+}
+
+impl JsonValue {
+    pub(crate) fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(*n),
+            _ => None
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None
+        }
+    }
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 2);
+    result.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c)
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+pub(crate) fn parse(text: &str) -> Result<JsonValue> {
+    let mut parser = Parser { chars: text.chars().collect(), position: 0 };
+
+    let value = parser.value()?;
+    parser.skip_whitespace();
+
+    if parser.position != parser.chars.len() {
+        bail!("Unexpected trailing characters at {}", parser.position);
+    }
+
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Result<char> {
+        let c = self.peek().ok_or_else(|| anyhow!("Unexpected end of input"))?;
+        self.position += 1;
+
+        Ok(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        let c = self.next()?;
+        if c != expected {
+            bail!("Expected '{expected}' but found '{c}' at {}", self.position - 1);
+        }
+
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: JsonValue) -> Result<JsonValue> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<JsonValue> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => Ok(JsonValue::String(self.string()?)),
+            Some('t') => self.keyword("true", JsonValue::Bool(true)),
+            Some('f') => self.keyword("false", JsonValue::Bool(false)),
+            Some('n') => self.keyword("null", JsonValue::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => bail!("Unexpected '{c}' at {}", self.position),
+            None => bail!("Unexpected end of input")
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonValue> {
+        self.expect('{')?;
+        let mut entries = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(JsonValue::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.value()?;
+            entries.push((key, value));
+
+            self.skip_whitespace();
+            match self.next()? {
+                ',' => continue,
+                '}' => break,
+                c => bail!("Expected ',' or '}}' but found '{c}' at {}", self.position - 1)
+            }
+        }
+
+        Ok(JsonValue::Object(entries))
+    }
+
+    fn array(&mut self) -> Result<JsonValue> {
+        self.expect('[')?;
+        let mut items = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(JsonValue::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+
+            self.skip_whitespace();
+            match self.next()? {
+                ',' => continue,
+                ']' => break,
+                c => bail!("Expected ',' or ']' but found '{c}' at {}", self.position - 1)
+            }
+        }
+
+        Ok(JsonValue::Array(items))
+    }
+
+    fn hex_escape(&mut self) -> Result<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.next()?.to_digit(16).ok_or_else(|| anyhow!("Invalid unicode escape at {}", self.position))?;
+            code = code * 16 + digit;
+        }
+
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut result = String::new();
+
+        loop {
+            match self.next()? {
+                '"' => break,
+                '\\' => {
+                    let c = match self.next()? {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let mut code = self.hex_escape()?;
+
+                            // Characters outside the BMP come as a surrogate pair
+                            if (0xD800..0xDC00).contains(&code) && self.peek() == Some('\\') {
+                                self.position += 1;
+                                self.expect('u')?;
+                                let low = self.hex_escape()?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        c => bail!("Invalid escape '\\{c}' at {}", self.position - 1)
+                    };
+
+                    result.push(c);
+                }
+                c => result.push(c)
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn number(&mut self) -> Result<JsonValue> {
+        let start = self.position;
+
+        while matches!(self.peek(), Some(c) if c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E' || c.is_ascii_digit()) {
+            self.position += 1;
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        let number = text.parse::<f64>().map_err(|_| anyhow!("Invalid number '{text}' at {start}"))?;
+
+        Ok(JsonValue::Number(number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaped_strings_parse_back_to_themselves() {
+        for text in ["plain", "say \"hi\"", "back\\slash", "lines\nand\r\ttabs", "\u{1b}[H\u{0}", "ünïcödé ✓ 🦀", ""] {
+            assert_eq!(parse(&escape(text)).unwrap(), JsonValue::String(text.to_string()), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn control_characters_are_escaped() {
+        assert_eq!(escape("\u{1b}[H"), "\"\\u001b[H\"");
+        assert_eq!(escape("a\"b"), "\"a\\\"b\"");
+    }
+
+    #[test]
+    fn unicode_escapes_decode_including_surrogate_pairs() {
+        assert_eq!(parse("\"\\u00e9\\u2713\"").unwrap(), JsonValue::String("é✓".to_string()));
+        assert_eq!(parse("\"\\ud83e\\udd80\"").unwrap(), JsonValue::String("🦀".to_string()));
+        assert_eq!(parse("\"\\/\\b\\f\"").unwrap(), JsonValue::String("/\u{8}\u{c}".to_string()));
+    }
+
+    #[test]
+    fn numbers_parse_in_every_notation() {
+        for (text, number) in [("0", 0.0), ("-12", -12.0), ("3.25", 3.25), ("1e3", 1000.0), ("-2.5E-1", -0.25), ("0.000001", 0.000001)] {
+            assert_eq!(parse(text).unwrap(), JsonValue::Number(number), "{text}");
+        }
+    }
+
+    #[test]
+    fn nested_values_parse() {
+        let value = parse(r#" {"version": 2, "env": {"TERM": "xterm"}, "events": [[0.5, "o", "x"], null, true, false]} "#).unwrap();
+
+        assert_eq!(value.get("version").and_then(JsonValue::as_f64), Some(2.0));
+        assert_eq!(value.get("env").and_then(|e| e.get("TERM")).and_then(JsonValue::as_str), Some("xterm"));
+
+        let events = value.get("events").and_then(JsonValue::as_array).unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].as_array().map(|e| e.len()), Some(3));
+        assert_eq!(events[1..], [JsonValue::Null, JsonValue::Bool(true), JsonValue::Bool(false)]);
+
+        assert_eq!(parse("[]").unwrap(), JsonValue::Array(vec![]));
+        assert_eq!(parse("{ }").unwrap(), JsonValue::Object(vec![]));
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        for text in ["", "\"unterminated", "[1, 2", "[1 2]", "{\"a\" 1}", "{\"a\": 1,}", "tru", "nul", "1.2.3", "-", "\"\\x\"", "\"\\u12\"", "[] []", "@"] {
+            assert!(parse(text).is_err(), "{text:?} parsed");
+        }
+    }
+}
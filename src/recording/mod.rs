@@ -0,0 +1,137 @@
+use std::{fs::File, io::BufWriter, path::Path, time::{Duration, Instant}};
+
+use anyhow::{Context, Result};
+
+use crate::{backend::{wake::Wake, Backend, BackendEvent, TerminalModes}, canvas::TextCanvas, layout::geometry::Size};
+
+pub mod cast;
+pub mod events;
+mod json;
+
+use cast::CastWriter;
+
+// Wraps another backend and writes every frame drawn through it to an asciinema cast.
+pub struct RecordingBackend<'b, B: Backend> {
+    inner: &'b mut B,
+    cast: CastWriter<BufWriter<File>>,
+    start: Instant,
+    // The size the cast's terminal is at
+    size: Size
+}
+
+impl<'b, B: Backend> RecordingBackend<'b, B> {
+    pub fn create(inner: &'b mut B, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("Couldn't create recording file {}", path.display()))?;
+
+        let size = inner.size()?;
+        let cast = CastWriter::new(BufWriter::new(file), &size)?;
+
+        Ok(RecordingBackend { inner, cast, start: Instant::now(), size })
+    }
+
+    fn elapsed_secs(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+}
+
+impl<B: Backend> Backend for RecordingBackend<'_, B> {
+    fn size(&self) -> Result<Size> {
+        self.inner.size()
+    }
+
+    fn draw(&mut self, canvas: &TextCanvas) -> Result<()> {
+        self.inner.draw(canvas)?;
+
+        let time = self.elapsed_secs();
+
+        // Resizes are recorded along with the first frame drawn at the new size, however the
+        // loop learned about them
+        if canvas.size() != &self.size {
+            self.size = canvas.size().clone();
+            self.cast.write_resize(time, &self.size)?;
+        }
+
+        self.cast.write_frame(time, canvas)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn poll_event(&mut self, timeout: Duration) -> Result<Option<BackendEvent>> {
+        self.inner.poll_event(timeout)
+    }
+
+    fn poll_event_or_wake(&mut self, timeout: Duration, wake: &Wake) -> Result<Option<BackendEvent>> {
+        self.inner.poll_event_or_wake(timeout, wake)
+    }
+
+    fn take_signal(&mut self) -> Result<Option<BackendEvent>> {
+        self.inner.take_signal()
+    }
+
+    fn enter(&mut self, modes: &TerminalModes) -> Result<()> {
+        self.inner.enter(modes)
+    }
+
+    // The terminal is handed back even when the cast couldn't be written out
+    fn leave(&mut self) -> Result<()> {
+        let left = self.inner.leave();
+        let flushed = self.cast.flush();
+
+        left.and(flushed)
+    }
+
+    fn suspend(&mut self) -> Result<()> {
+        self.inner.suspend()
+    }
+
+    fn can_suspend(&self) -> bool {
+        self.inner.can_suspend()
+    }
+
+    fn panic_restore(&self) -> Box<dyn Fn() + Send + Sync> {
+        self.inner.panic_restore()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::memory::MemoryBackend;
+
+    use super::*;
+
+    #[test]
+    fn frames_drawn_through_it_end_up_in_the_cast() {
+        let path = std::env::temp_dir().join(format!("textyle-recording-{}.cast", std::process::id()));
+
+        let mut backend = MemoryBackend::new(2, 1);
+        let mut first = TextCanvas::create(2, 1);
+        first.write("a", 0, 0);
+        let mut resized = TextCanvas::create(3, 1);
+        resized.write("b", 2, 0);
+
+        {
+            let mut recording = RecordingBackend::create(&mut backend, &path).unwrap();
+            recording.enter(&TerminalModes::default()).unwrap();
+            recording.draw(&first).unwrap();
+            recording.draw(&resized).unwrap();
+            recording.leave().unwrap();
+        }
+
+        assert!(backend.modes().is_none());
+        assert_eq!(backend.frame().to_string(), "  b");
+
+        let cast = cast::Cast::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((cast.width, cast.height), (2, 1));
+
+        let frames = cast.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].canvas.to_string(), "a ");
+        assert_eq!(frames[1].canvas.to_string(), "  b");
+    }
+}
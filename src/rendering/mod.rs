@@ -1,14 +1,36 @@
 use crate::layout::{self, geometry::Rect, SizedLayout};
 
-#[derive(Debug)]
+pub mod state;
+
+use state::RenderState;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum DrawCommand {
     Text(Rect, String),
     FillRect(Rect, String),
     StrokeRect(Rect, usize, String),
+    Cursor(i64, i64)
+}
+
+impl DrawCommand {
+    pub fn translated(&self, dx: i64, dy: i64) -> DrawCommand {
+        let offset = |bounds: &Rect| Rect::new(bounds.x + dx, bounds.y + dy, bounds.width, bounds.height);
+
+        match self {
+            DrawCommand::Text(bounds, text) => DrawCommand::Text(offset(bounds), text.clone()),
+            DrawCommand::FillRect(bounds, grapheme) => DrawCommand::FillRect(offset(bounds), grapheme.clone()),
+            DrawCommand::StrokeRect(bounds, n, grapheme) => DrawCommand::StrokeRect(offset(bounds), *n, grapheme.clone()),
+            DrawCommand::Cursor(x, y) => DrawCommand::Cursor(x + dx, y + dy)
+        }
+    }
 }
 
 impl<Ctx: Clone> SizedLayout<Ctx> {
     pub fn resolve_draw_commands(&self, bounds: &Rect, context: &mut Ctx) -> Vec<DrawCommand> {
+        self.resolve_draw_commands_with_state(bounds, context, &mut RenderState::new())
+    }
+
+    pub fn resolve_draw_commands_with_state(&self, bounds: &Rect, context: &mut Ctx, state: &mut RenderState<Ctx>) -> Vec<DrawCommand> {
         use layout::SizedNode::*;
         let layout = self.clone();
 
@@ -19,7 +41,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
             Width(_, node) | Height(_, node) => {
                 let frame = node.sizing.fit_into(bounds);
 
-                node.resolve_draw_commands(&frame, context)
+                node.resolve_draw_commands_with_state(&frame, context, state)
             }
             VCenter(n) => {
                 let mut content_rect = n.sizing.fit_into(bounds);
@@ -29,7 +51,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
 
                 let content_bounds = n.sizing.fit_into(&content_rect);
 
-                n.resolve_draw_commands(&content_bounds, context)
+                n.resolve_draw_commands_with_state(&content_bounds, context, state)
             }
             HCenter(n) => {
                 let mut content_rect = n.sizing.fit_into(bounds);
@@ -39,7 +61,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
 
                 let content_bounds = n.sizing.fit_into(&content_rect);
 
-                n.resolve_draw_commands(&content_bounds, context)
+                n.resolve_draw_commands_with_state(&content_bounds, context, state)
             }
             VBottomAlign(n) => {
                 let mut content_rect = n.sizing.fit_into(bounds);
@@ -47,7 +69,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
                 let top_start = bottom_most - content_rect.height;
                 content_rect.y = top_start as i64;
 
-                n.resolve_draw_commands(&content_rect, context)
+                n.resolve_draw_commands_with_state(&content_rect, context, state)
             }
             HRightAlign(n) => {
                 let mut content_rect = n.sizing.fit_into(bounds);
@@ -57,12 +79,12 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
 
                 let content_bounds = n.sizing.fit_into(&content_rect);
 
-                n.resolve_draw_commands(&content_bounds, context)
+                n.resolve_draw_commands_with_state(&content_bounds, context, state)
             }
             VTopAlign(n) | HLeftAlign(n) => {
                 let content_rect = n.sizing.fit_into(bounds);
 
-                n.resolve_draw_commands(&content_rect, context)
+                n.resolve_draw_commands_with_state(&content_rect, context, state)
             }
             TopPadding(n, node) => {
                 let mut bounds = bounds.clone();
@@ -71,7 +93,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
                 frame.x = bounds.x;
                 frame.y = bounds.y + n as i64;
 
-                node.resolve_draw_commands(&frame, context)
+                node.resolve_draw_commands_with_state(&frame, context, state)
             }
             BottomPadding(n, node) => {
                 let mut bounds = bounds.clone();
@@ -81,7 +103,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
                 frame.x = bounds.x;
                 frame.y = bounds.y;
 
-                node.resolve_draw_commands(&frame, context)
+                node.resolve_draw_commands_with_state(&frame, context, state)
             }
             RightPadding(n, node) => {
                 let mut frame = node.sizing.fit_into(bounds);
@@ -93,7 +115,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
 
                 frame.width = frame.width.saturating_sub(adjustment);
 
-                node.resolve_draw_commands(&frame, context)
+                node.resolve_draw_commands_with_state(&frame, context, state)
             }
             LeftPadding(n, node) => {
                 let mut bounds = bounds.clone();
@@ -102,7 +124,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
                 frame.x = bounds.x + n as i64;
                 frame.y = bounds.y;
 
-                node.resolve_draw_commands(&frame, context)
+                node.resolve_draw_commands_with_state(&frame, context, state)
             }
             Background(c, node) => {
                 let mut frame = node.sizing.fit_into(bounds);
@@ -112,7 +134,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
                 // self.draw_rect(bounds, &c.to_string());
                 let mut commands = vec![DrawCommand::FillRect(bounds.clone(), c.to_string())];
 
-                let content_commands = node.resolve_draw_commands(&frame, context);
+                let content_commands = node.resolve_draw_commands_with_state(&frame, context, state);
 
                 commands.extend(content_commands);
 
@@ -144,7 +166,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
                 frame.x = inner_bounds.x;
                 frame.y = inner_bounds.y;
 
-                let mut commands = node.resolve_draw_commands(&frame, context);
+                let mut commands = node.resolve_draw_commands_with_state(&frame, context, state);
 
                 if edges == layout::alignment::Edge::all() {
                     commands.push(DrawCommand::StrokeRect(outer_bounds.clone(), n, c.to_string()));
@@ -198,7 +220,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
                 }
 
                 let mut greedy_space = bounds.height - static_height;
-                let greedy_size = if greedy_count != 0 { greedy_space / greedy_count } else { 0 };
+                let greedy_size = greedy_space.checked_div(greedy_count).unwrap_or(0);
 
                 let mut new_nodes = vec![];
 
@@ -270,7 +292,11 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
                 nodes.into_iter().enumerate().flat_map(|(i, node)| {
                     let size = &final_bounds[i];
 
-                    node.resolve_draw_commands(size, context)
+                    state.push_path(&i.to_string());
+                    let commands = node.resolve_draw_commands_with_state(size, context, state);
+                    state.pop_path();
+
+                    commands
                 }).collect::<Vec<_>>()
             }
             HorizontalStack(alignment, spacing, nodes) => {
@@ -292,7 +318,7 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
                 }
 
                 let mut greedy_space = bounds.width.saturating_sub(static_width);
-                let greedy_size = if greedy_count != 0 { greedy_space / greedy_count } else { 0 };
+                let greedy_size = greedy_space.checked_div(greedy_count).unwrap_or(0);
 
                 let mut new_nodes = vec![];
 
@@ -364,7 +390,11 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
                 nodes.into_iter().enumerate().flat_map(|(i, node)| {
                     let size = &final_bounds[i];
 
-                    node.resolve_draw_commands(size, context)
+                    state.push_path(&i.to_string());
+                    let commands = node.resolve_draw_commands_with_state(size, context, state);
+                    state.pop_path();
+
+                    commands
                 }).collect::<Vec<_>>()
             }
             DrawCanvas(action) => {
@@ -372,6 +402,43 @@ impl<Ctx: Clone> SizedLayout<Ctx> {
 
                 vec![DrawCommand::Text(bounds.clone(), result.to_string())]
             }
+            Widget(widget) => {
+                let result = widget.render(&bounds.size(), context);
+
+                let mut commands = vec![DrawCommand::Text(bounds.clone(), result.to_string())];
+                if let Some((x, y)) = result.cursor() {
+                    commands.push(DrawCommand::Cursor(bounds.x + x as i64, bounds.y + y as i64));
+                }
+
+                commands
+            }
+            Identified(id, node) => {
+                let path = state.replace_path(&id);
+                let commands = node.resolve_draw_commands_with_state(bounds, context, state);
+                state.restore_path(path);
+
+                commands
+            }
+            Animation(easing, duration, node) => {
+                let frame = state.animated_frame(bounds, easing, duration);
+
+                node.resolve_draw_commands_with_state(&frame, context, state)
+            }
+            Transition(kind, easing, duration, node) => {
+                let commands = node.resolve_draw_commands_with_state(bounds, context, state);
+                let visibility = state.presence(&kind, easing, duration, &commands, bounds);
+
+                if visibility >= 1.0 {
+                    commands
+                } else {
+                    state::transition_commands(&kind, visibility, &commands, bounds)
+                }
+            }
+            Focusable(id, on_key, node) => {
+                state.register_focusable(&id, on_key);
+
+                node.resolve_draw_commands_with_state(bounds, context, state)
+            }
         }
     }
 }
\ No newline at end of file
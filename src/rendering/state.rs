@@ -0,0 +1,379 @@
+use std::{collections::{HashMap, HashSet}, time::Duration};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{animation::tween::Animated, easing::Easing, layout::{alignment::Edge, focus::KeyHandler, geometry::Rect, transition::Transition}};
+
+use super::DrawCommand;
+
+struct Presence {
+    kind: Transition,
+    visibility: Animated<f64>,
+    easing: Easing,
+    duration: Duration,
+    // What was drawn last, kept around so the node can still be drawn while it leaves
+    last_commands: Vec<DrawCommand>,
+    last_bounds: Rect,
+    leaving: bool
+}
+
+// Everything rendering needs to remember from one frame to the next.
+pub struct RenderState<Ctx> {
+    path: Vec<String>,
+    has_rendered: bool,
+    frames: HashMap<String, Animated<Rect>>,
+    presences: HashMap<String, Presence>,
+    seen: HashSet<String>,
+    focused: Option<String>,
+    focus_order: Vec<String>,
+    key_handlers: HashMap<String, KeyHandler<Ctx>>
+}
+
+impl<Ctx> Default for RenderState<Ctx> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Ctx> RenderState<Ctx> {
+    pub fn new() -> Self {
+        RenderState {
+            path: vec![],
+            has_rendered: false,
+            frames: HashMap::new(),
+            presences: HashMap::new(),
+            seen: HashSet::new(),
+            focused: None,
+            focus_order: vec![],
+            key_handlers: HashMap::new()
+        }
+    }
+
+    pub fn advance(&mut self, delta_milis: f64) {
+        for frame in self.frames.values_mut() {
+            frame.tick(delta_milis);
+        }
+
+        for presence in self.presences.values_mut() {
+            presence.visibility.tick(delta_milis);
+        }
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.frames.values().any(|f| !f.is_finished())
+            || self.presences.values().any(|p| !p.visibility.is_finished())
+    }
+}
+
+// Node identity
+impl<Ctx> RenderState<Ctx> {
+    pub(crate) fn key(&self) -> String {
+        self.path.join("/")
+    }
+
+    pub(crate) fn push_path(&mut self, segment: &str) {
+        self.path.push(segment.to_string());
+    }
+
+    pub(crate) fn pop_path(&mut self) {
+        self.path.pop();
+    }
+
+    // Explicit ids replace the structural path, so the node keeps its identity when siblings come and go
+    pub(crate) fn replace_path(&mut self, id: &str) -> Vec<String> {
+        std::mem::replace(&mut self.path, vec![format!("#{id}")])
+    }
+
+    pub(crate) fn restore_path(&mut self, path: Vec<String>) {
+        self.path = path;
+    }
+}
+
+// Focus
+impl<Ctx> RenderState<Ctx> {
+    // Starts collecting the focusable nodes of a new frame, styled according to `focused`
+    pub fn start_focus_scan(&mut self, focused: Option<&str>) {
+        self.focused = focused.map(str::to_string);
+        self.focus_order.clear();
+        self.key_handlers.clear();
+    }
+
+    // The focusable nodes drawn since the scan started, in layout order
+    pub fn focus_order(&self) -> &[String] {
+        &self.focus_order
+    }
+
+    pub fn key_handler(&self, id: &str) -> Option<KeyHandler<Ctx>> {
+        self.key_handlers.get(id).copied()
+    }
+
+    pub(crate) fn key_handlers(&self) -> &HashMap<String, KeyHandler<Ctx>> {
+        &self.key_handlers
+    }
+
+    pub(crate) fn is_focused(&self, id: &str) -> bool {
+        self.focused.as_deref() == Some(id)
+    }
+
+    pub(crate) fn register_focusable(&mut self, id: &str, on_key: Option<KeyHandler<Ctx>>) {
+        if !self.focus_order.iter().any(|known| known == id) {
+            self.focus_order.push(id.to_string());
+        }
+
+        if let Some(handler) = on_key {
+            self.key_handlers.insert(id.to_string(), handler);
+        }
+    }
+}
+
+// Transitions
+impl<Ctx> RenderState<Ctx> {
+    pub(crate) fn animated_frame(&mut self, target: &Rect, easing: Easing, duration: Duration) -> Rect {
+        let key = self.key();
+        self.seen.insert(key.clone());
+
+        let frame = self.frames.entry(key)
+            .or_insert_with(|| Animated::new(target.clone()));
+
+        if frame.target() != target {
+            frame.animate_to(target.clone(), duration, easing);
+        }
+
+        frame.value()
+    }
+
+    pub(crate) fn presence(&mut self, kind: &Transition, easing: Easing, duration: Duration, commands: &[DrawCommand], bounds: &Rect) -> f64 {
+        let key = self.key();
+        self.seen.insert(key.clone());
+
+        // Nodes that are there from the very first frame don't animate in
+        let initial_visibility = if self.has_rendered { 0.0 } else { 1.0 };
+
+        let presence = self.presences.entry(key).or_insert_with(|| Presence {
+            kind: kind.clone(),
+            visibility: Animated::new(initial_visibility),
+            easing,
+            duration,
+            last_commands: vec![],
+            last_bounds: bounds.clone(),
+            leaving: true
+        });
+
+        if presence.leaving {
+            presence.visibility.animate_to(1.0, duration, easing);
+            presence.leaving = false;
+        }
+
+        presence.kind = kind.clone();
+        presence.easing = easing;
+        presence.duration = duration;
+        presence.last_commands = commands.to_vec();
+        presence.last_bounds = bounds.clone();
+
+        presence.visibility.value()
+    }
+
+    // Draws the nodes that left the layout this frame and are still transitioning out
+    pub(crate) fn finish_frame(&mut self) -> Vec<DrawCommand> {
+        let seen = std::mem::take(&mut self.seen);
+
+        self.frames.retain(|key, _| seen.contains(key));
+
+        self.presences.retain(|key, presence| {
+            if seen.contains(key) { return true; }
+
+            if !presence.leaving {
+                presence.visibility.animate_to(0.0, presence.duration, presence.easing);
+                presence.leaving = true;
+            }
+
+            presence.visibility.value() > 0.0
+        });
+
+        self.has_rendered = true;
+
+        self.presences.values()
+            .filter(|p| p.leaving)
+            .flat_map(|p| transition_commands(&p.kind, p.visibility.value(), &p.last_commands, &p.last_bounds))
+            .collect()
+    }
+}
+
+// Redraws the commands of a node laid out in `bounds` partially shown according to `visibility`,
+// where 0 is hidden and 1 is fully in place.
+pub(crate) fn transition_commands(kind: &Transition, visibility: f64, commands: &[DrawCommand], bounds: &Rect) -> Vec<DrawCommand> {
+    let visibility = visibility.clamp(0.0, 1.0);
+
+    let width = bounds.width;
+    let height = bounds.height;
+    let hidden_width = ((1.0 - visibility) * width as f64).round() as usize;
+    let hidden_height = ((1.0 - visibility) * height as f64).round() as usize;
+
+    // (source origin in the content, destination origin relative to bounds, visible size)
+    let (source, destination, size) = match kind {
+        Transition::Reveal => ((0, 0), (0, 0), (width - hidden_width, height)),
+        Transition::Slide(Edge::Left) => ((hidden_width, 0), (0, 0), (width - hidden_width, height)),
+        Transition::Slide(Edge::Right) => ((0, 0), (hidden_width, 0), (width - hidden_width, height)),
+        Transition::Slide(Edge::Top) => ((0, hidden_height), (0, 0), (width, height - hidden_height)),
+        Transition::Slide(Edge::Bottom) => ((0, 0), (0, hidden_height), (width, height - hidden_height))
+    };
+
+    if size.0 == 0 || size.1 == 0 { return vec![]; }
+
+    let visible = Rect::new(bounds.x + source.0 as i64, bounds.y + source.1 as i64, size.0, size.1);
+    let dx = destination.0 as i64 - source.0 as i64;
+    let dy = destination.1 as i64 - source.1 as i64;
+
+    commands.iter()
+        .flat_map(|command| clip_command(command, &visible))
+        .map(|command| command.translated(dx, dy))
+        .collect()
+}
+
+// The part of a command that falls inside `clip`. Commands keep their kind, so fills still
+// cover whatever is under them.
+fn clip_command(command: &DrawCommand, clip: &Rect) -> Vec<DrawCommand> {
+    match command {
+        DrawCommand::FillRect(bounds, grapheme) => bounds.intersection(clip)
+            .map(|bounds| DrawCommand::FillRect(bounds, grapheme.clone()))
+            .into_iter()
+            .collect(),
+        DrawCommand::StrokeRect(bounds, n, grapheme) => {
+            let across = (*n).min(bounds.width);
+            let down = (*n).min(bounds.height);
+
+            // The same cells as the four sides of the stroke
+            let sides = [
+                Rect::new(bounds.x, bounds.y, bounds.width, down),
+                Rect::new(bounds.x, bounds.max_y() - down as i64, bounds.width, down),
+                Rect::new(bounds.x, bounds.y, across, bounds.height),
+                Rect::new(bounds.max_x() - across as i64, bounds.y, across, bounds.height)
+            ];
+
+            sides.iter()
+                .filter_map(|side| side.intersection(clip))
+                .map(|side| DrawCommand::FillRect(side, grapheme.clone()))
+                .collect()
+        }
+        DrawCommand::Text(bounds, text) => clip_text(bounds, text, clip),
+        DrawCommand::Cursor(x, y) => {
+            if clip.contains(*x, *y) { vec![command.clone()] } else { vec![] }
+        }
+    }
+}
+
+// Lays the text out the way the canvas would, then keeps one command per row for the cells
+// inside `clip`. Spaces stay in, they're just as transparent as before.
+fn clip_text(bounds: &Rect, text: &str, clip: &Rect) -> Vec<DrawCommand> {
+    let mut rows: Vec<(i64, i64, String)> = vec![];
+
+    let mut x = bounds.x;
+    let mut y = bounds.y;
+
+    let mut graphemes = text.graphemes(true).peekable();
+
+    while let Some(grapheme) = graphemes.next() {
+        if grapheme == "\n" {
+            y += 1;
+            x = bounds.x;
+            continue;
+        }
+
+        if clip.contains(x, y) {
+            match rows.last_mut() {
+                Some((row_y, _, row)) if *row_y == y => row.push_str(grapheme),
+                _ => rows.push((y, x, grapheme.to_string()))
+            }
+        }
+
+        x += 1;
+        if (x - bounds.x) as usize >= bounds.width {
+            y += 1;
+            x = bounds.x;
+
+            if graphemes.peek() == Some(&"\n") {
+                graphemes.next();
+            }
+        }
+    }
+
+    rows.into_iter()
+        .map(|(y, x, row)| DrawCommand::Text(Rect::new(x, y, row.graphemes(true).count(), 1), row))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::TextCanvas;
+
+    use super::*;
+
+    fn draw(commands: &[DrawCommand], width: usize, height: usize) -> String {
+        let mut canvas = TextCanvas::create(width, height);
+        canvas.clear_with(".");
+        canvas.execute_draw_commands(commands);
+
+        canvas.to_string()
+    }
+
+    #[test]
+    fn frames_move_towards_a_new_target_over_the_duration() {
+        let mut state = RenderState::<()>::new();
+        let duration = Duration::from_millis(100);
+
+        assert_eq!(state.animated_frame(&Rect::new(0, 0, 2, 2), Easing::Linear, duration), Rect::new(0, 0, 2, 2));
+        assert_eq!(state.animated_frame(&Rect::new(10, 4, 6, 2), Easing::Linear, duration), Rect::new(0, 0, 2, 2));
+
+        state.advance(50.0);
+        assert!(state.is_animating());
+        assert_eq!(state.animated_frame(&Rect::new(10, 4, 6, 2), Easing::Linear, duration), Rect::new(5, 2, 4, 2));
+
+        state.advance(50.0);
+        assert!(!state.is_animating());
+        assert_eq!(state.animated_frame(&Rect::new(10, 4, 6, 2), Easing::Linear, duration), Rect::new(10, 4, 6, 2));
+    }
+
+    #[test]
+    fn fills_stay_fills_while_transitioning() {
+        let commands = vec![DrawCommand::FillRect(Rect::new(0, 0, 4, 1), " ".to_string())];
+
+        let clipped = transition_commands(&Transition::Reveal, 0.5, &commands, &Rect::new(0, 0, 4, 1));
+
+        assert_eq!(clipped, vec![DrawCommand::FillRect(Rect::new(0, 0, 2, 1), " ".to_string())]);
+        assert_eq!(draw(&clipped, 4, 1), "  ..");
+    }
+
+    #[test]
+    fn sliding_in_from_the_left_shows_the_right_end_first() {
+        let commands = vec![DrawCommand::Text(Rect::new(1, 0, 4, 2), "abcdefgh".to_string())];
+
+        let clipped = transition_commands(&Transition::Slide(Edge::Left), 0.5, &commands, &Rect::new(1, 0, 4, 2));
+
+        assert_eq!(draw(&clipped, 6, 2), ".cd...\n.gh...");
+    }
+
+    #[test]
+    fn sliding_in_from_the_bottom_moves_the_top_rows_down() {
+        let commands = vec![
+            DrawCommand::StrokeRect(Rect::new(0, 0, 3, 3), 1, "#".to_string()),
+            DrawCommand::Cursor(1, 1)
+        ];
+
+        let clipped = transition_commands(&Transition::Slide(Edge::Bottom), 0.34, &commands, &Rect::new(0, 0, 3, 3));
+
+        assert_eq!(draw(&clipped, 3, 3), "...\n...\n###");
+        assert!(!clipped.contains(&DrawCommand::Cursor(1, 3)));
+    }
+
+    #[test]
+    fn a_fully_visible_node_draws_the_same() {
+        let commands = vec![
+            DrawCommand::FillRect(Rect::new(0, 0, 5, 2), "-".to_string()),
+            DrawCommand::Text(Rect::new(0, 0, 5, 2), "one\ntwo".to_string())
+        ];
+
+        let clipped = transition_commands(&Transition::Reveal, 1.0, &commands, &Rect::new(0, 0, 5, 2));
+
+        assert_eq!(draw(&clipped, 5, 2), draw(&commands, 5, 2));
+    }
+}
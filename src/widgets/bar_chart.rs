@@ -0,0 +1,347 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{canvas::TextCanvas, layout::{geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}}};
+
+use super::{progress::{fill, LEFT_EIGHTHS, LOWER_EIGHTHS}, Widget};
+
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 { format!("{value:.0}") } else { format!("{value:.1}") }
+}
+
+fn write_text(canvas: &mut TextCanvas, text: &str, x: usize, y: usize, width: usize) {
+    for (i, grapheme) in text.graphemes(true).take(width).enumerate() {
+        canvas.write(grapheme, x + i, y);
+    }
+}
+
+// Where each tick of a value axis `cells` long goes, counted from the zero end, and its label.
+// Zero, the max, and the middle when there's a cell for it.
+fn axis_ticks(cells: usize, max: f64) -> Vec<(usize, String)> {
+    match cells {
+        0 => vec![],
+        1 => vec![(0, format_value(max))],
+        _ => {
+            let mut ticks = vec![(0, format_value(0.0)), (cells - 1, format_value(max))];
+
+            if cells >= 3 {
+                let middle = (cells - 1) / 2;
+                ticks.push((middle, format_value(max * middle as f64 / (cells - 1) as f64)));
+            }
+
+            ticks
+        }
+    }
+}
+
+// Groups of bars, each group with an optional category label
+pub struct BarChart {
+    groups: Vec<Vec<f64>>,
+    labels: Vec<String>,
+    max: Option<f64>,
+    horizontal: bool,
+    bar_width: usize,
+    bar_gap: usize,
+    group_gap: usize,
+    value_labels: bool,
+    value_axis: bool
+}
+
+impl BarChart {
+    // A bar per value
+    pub fn new(data: &[f64]) -> Self {
+        Self::grouped(&data.iter().map(|v| vec![*v]).collect::<Vec<_>>())
+    }
+
+    // Bars in the same group are drawn next to each other
+    pub fn grouped(groups: &[Vec<f64>]) -> Self {
+        BarChart {
+            groups: groups.to_vec(),
+            labels: vec![],
+            max: None,
+            horizontal: false,
+            bar_width: 1,
+            bar_gap: 0,
+            group_gap: 1,
+            value_labels: false,
+            value_axis: false
+        }
+    }
+
+    // Category labels, one per group, under vertical bars or before horizontal ones
+    pub fn labels(mut self, labels: &[&str]) -> Self {
+        self.labels = labels.iter().map(|l| l.to_string()).collect();
+        self
+    }
+
+    // The value of a full bar. Defaults to the largest value.
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn horizontal(mut self) -> Self {
+        self.horizontal = true;
+        self
+    }
+
+    // How many cells across each bar is
+    pub fn bar_width(mut self, width: usize) -> Self {
+        self.bar_width = width.max(1);
+        self
+    }
+
+    pub fn bar_gap(mut self, gap: usize) -> Self {
+        self.bar_gap = gap;
+        self
+    }
+
+    pub fn group_gap(mut self, gap: usize) -> Self {
+        self.group_gap = gap;
+        self
+    }
+
+    // Shows each value at the end of its bar
+    pub fn value_labels(mut self) -> Self {
+        self.value_labels = true;
+        self
+    }
+
+    // Ticks with the values along the bars, on the left of vertical ones and under horizontal ones
+    pub fn value_axis(mut self) -> Self {
+        self.value_axis = true;
+        self
+    }
+
+    fn max_value(&self) -> f64 {
+        self.max.unwrap_or_else(|| self.groups.iter().flatten().copied().fold(0.0, f64::max))
+    }
+
+    // Where each group starts along the category axis, and how long it is
+    fn group_spans(&self) -> Vec<(usize, usize)> {
+        let mut position = 0;
+
+        self.groups.iter()
+            .map(|group| {
+                let length = group.len() * self.bar_width + group.len().saturating_sub(1) * self.bar_gap;
+                let span = (position, length);
+                position += length + self.group_gap;
+
+                span
+            })
+            .collect()
+    }
+
+    fn render_vertical(&self, canvas: &mut TextCanvas, size: &Size, max: f64) {
+        let has_labels = !self.labels.is_empty();
+        let height = size.height.saturating_sub(usize::from(has_labels));
+
+        let axis_width = if self.value_axis {
+            let ticks = axis_ticks(height, max);
+            let tick_width = ticks.iter().map(|(_, text)| text.len()).max().unwrap_or(0);
+
+            for y in 0..height {
+                canvas.write("│", tick_width, y);
+            }
+
+            // Right aligned against the axis
+            for (position, text) in ticks {
+                let y = height - 1 - position;
+                write_text(canvas, &text, tick_width - text.len(), y, text.len());
+                canvas.write("┤", tick_width, y);
+            }
+
+            tick_width + 1
+        } else {
+            0
+        };
+
+        for (group_index, ((start, length), group)) in self.group_spans().into_iter().zip(self.groups.iter()).enumerate() {
+            let start = axis_width + start;
+
+            for (i, value) in group.iter().enumerate() {
+                let x = start + i * (self.bar_width + self.bar_gap);
+                let column = fill(value / max, height, &LOWER_EIGHTHS);
+                let top = column.iter().rposition(|glyph| *glyph != " ");
+
+                for (row, glyph) in column.into_iter().enumerate() {
+                    canvas.draw_rect(&Rect::new(x as i64, (height - 1 - row) as i64, self.bar_width, 1), glyph);
+                }
+
+                if self.value_labels && height > 0 {
+                    // Right above the bar, or over its top when it's full
+                    let y = match top {
+                        Some(top) => (height - 1 - top).saturating_sub(1),
+                        None => height - 1
+                    };
+                    // Centred on the bar, spilling over the gaps when wider
+                    let text = format_value(*value);
+                    let text_x = (x + self.bar_width / 2).saturating_sub(text.len() / 2);
+                    write_text(canvas, &text, text_x, y, text.len());
+                }
+            }
+
+            if let Some(label) = self.labels.get(group_index) {
+                let label_width = label.graphemes(true).count().min(length);
+                write_text(canvas, label, start + (length - label_width) / 2, size.height - 1, length);
+            }
+        }
+    }
+
+    fn render_horizontal(&self, canvas: &mut TextCanvas, size: &Size, max: f64) {
+        let label_width = self.labels.iter()
+            .map(|l| l.graphemes(true).count() + 1)
+            .max()
+            .unwrap_or(0);
+
+        let value_width = if self.value_labels {
+            self.groups.iter().flatten().map(|v| format_value(*v).len() + 1).max().unwrap_or(0)
+        } else {
+            0
+        };
+
+        let length = size.width.saturating_sub(label_width + value_width);
+
+        for (group_index, ((start, _), group)) in self.group_spans().into_iter().zip(self.groups.iter()).enumerate() {
+            if let Some(label) = self.labels.get(group_index) {
+                write_text(canvas, label, 0, start, label_width);
+            }
+
+            for (i, value) in group.iter().enumerate() {
+                let y = start + i * (self.bar_width + self.bar_gap);
+                let row = fill(value / max, length, &LEFT_EIGHTHS);
+                let end = row.iter().rposition(|glyph| *glyph != " ").map_or(0, |x| x + 1);
+
+                for (x, glyph) in row.into_iter().enumerate() {
+                    canvas.draw_rect(&Rect::new((label_width + x) as i64, y as i64, 1, self.bar_width), glyph);
+                }
+
+                if self.value_labels {
+                    write_text(canvas, &format_value(*value), label_width + end + 1, y, value_width);
+                }
+            }
+        }
+
+        if self.value_axis && size.height >= 2 {
+            self.render_horizontal_axis(canvas, label_width, length, size.height - 2, max);
+        }
+    }
+
+    // A line with the ticks and their values under it, over whatever bars didn't fit above
+    fn render_horizontal_axis(&self, canvas: &mut TextCanvas, x: usize, length: usize, y: usize, max: f64) {
+        for i in 0..length {
+            canvas.write("─", x + i, y);
+        }
+
+        // Zero and the max go first, the middle only gets drawn if there's room left between them
+        let mut ticks = axis_ticks(length, max);
+        ticks.sort_by_key(|(position, _)| *position != 0 && *position != length - 1);
+
+        let mut taken: Vec<(usize, usize)> = vec![];
+
+        for (position, text) in ticks {
+            let start = if position == 0 {
+                0
+            } else if position == length - 1 {
+                (position + 1).saturating_sub(text.len())
+            } else {
+                position.saturating_sub(text.len() / 2)
+            };
+            let end = start + text.len();
+
+            if end > length || taken.iter().any(|(s, e)| start <= *e && *s <= end) { continue; }
+            taken.push((start, end));
+
+            canvas.write("┬", x + position, y);
+            write_text(canvas, &text, x + start, y + 1, text.len());
+        }
+    }
+}
+
+impl<Ctx> Widget<Ctx> for BarChart {
+    fn sizing(&self, _bounds: &Rect, _context: &mut Ctx) -> ItemSizing {
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Greedy(1))
+    }
+
+    fn render(&self, size: &Size, _context: &mut Ctx) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+
+        let max = self.max_value();
+        if max <= 0.0 || size.width == 0 || size.height == 0 { return canvas; }
+
+        if self.horizontal {
+            self.render_horizontal(&mut canvas, size, max);
+        } else {
+            self.render_vertical(&mut canvas, size, max);
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(chart: &BarChart, width: usize, height: usize) -> Vec<String> {
+        Widget::<()>::render(chart, &Size::new(width, height), &mut ()).to_string().lines()
+            .map(|line| line.trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn nothing_is_drawn_without_room() {
+        let chart = BarChart::new(&[1.0, 2.0]).labels(&["a", "b"]).value_labels();
+
+        assert!(rendered(&chart, 0, 0).is_empty());
+        assert!(rendered(&chart, 4, 0).is_empty());
+        assert_eq!(rendered(&chart.horizontal(), 0, 3), Vec::<String>::new());
+    }
+
+    #[test]
+    fn vertical_bars_stand_on_their_category_labels() {
+        let chart = BarChart::new(&[1.0, 2.0]).labels(&["a", "b"]);
+
+        assert_eq!(rendered(&chart, 3, 3), vec![
+            "  █",
+            "█ █",
+            "a b"
+        ]);
+    }
+
+    #[test]
+    fn horizontal_bars_follow_their_category_labels() {
+        let chart = BarChart::new(&[2.0, 4.0]).labels(&["a", "bb"]).horizontal().value_labels().group_gap(0);
+
+        assert_eq!(rendered(&chart, 9, 2), vec![
+            "a  ██ 2",
+            "bb ████ 4"
+        ]);
+    }
+
+    #[test]
+    fn a_value_axis_goes_left_of_vertical_bars() {
+        let chart = BarChart::new(&[2.0, 4.0]).labels(&["a", "b"]).value_axis();
+
+        assert_eq!(rendered(&chart, 6, 4), vec![
+            "4┤  █",
+            "2┤▄ █",
+            "0┤█ █",
+            "  a b"
+        ]);
+    }
+
+    #[test]
+    fn a_value_axis_goes_under_horizontal_bars() {
+        let chart = BarChart::new(&[2.0, 4.0]).horizontal().value_axis().group_gap(0);
+
+        assert_eq!(rendered(&chart, 9, 4), vec![
+            "████▌",
+            "█████████",
+            "┬───┬───┬",
+            "0   2   4"
+        ]);
+
+        // No room for the middle tick's value
+        assert_eq!(rendered(&chart, 4, 4)[2..], ["┬──┬", "0  4"]);
+    }
+}
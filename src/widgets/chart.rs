@@ -0,0 +1,449 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{canvas::TextCanvas, layout::{geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}}};
+
+use super::Widget;
+
+// The dots of a braille cell, indexed by row then column
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+const BRAILLE_BLANK: u32 = 0x2800;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Marker {
+    // Two by four dots per cell
+    Braille,
+    // Two dots per cell, one above the other
+    HalfBlock,
+    // One dot per cell, drawn as the character
+    Char(char)
+}
+
+impl Marker {
+    // How many dots fit in a cell, across and down
+    fn resolution(&self) -> (usize, usize) {
+        match self {
+            Marker::Braille => (2, 4),
+            Marker::HalfBlock => (1, 2),
+            Marker::Char(_) => (1, 1)
+        }
+    }
+
+    fn symbol(&self) -> String {
+        match self {
+            Marker::Braille => "⣿".to_string(),
+            Marker::HalfBlock => "█".to_string(),
+            Marker::Char(c) => c.to_string()
+        }
+    }
+
+    // The glyph for a cell, given which of its dots are set
+    fn glyph(&self, dots: &[Vec<bool>]) -> Option<String> {
+        if !dots.iter().flatten().any(|dot| *dot) { return None; }
+
+        let glyph = match self {
+            Marker::Braille => {
+                let bits = dots.iter().enumerate()
+                    .flat_map(|(y, row)| row.iter().enumerate().filter(|(_, dot)| **dot).map(move |(x, _)| BRAILLE_DOTS[y][x]))
+                    .sum::<u32>();
+
+                char::from_u32(BRAILLE_BLANK + bits).unwrap_or(' ').to_string()
+            }
+            Marker::HalfBlock => match (dots[0][0], dots[1][0]) {
+                (true, true) => "█".to_string(),
+                (true, false) => "▀".to_string(),
+                _ => "▄".to_string()
+            },
+            Marker::Char(c) => c.to_string()
+        };
+
+        Some(glyph)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphType {
+    Line,
+    Scatter
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dataset {
+    name: String,
+    points: Vec<(f64, f64)>,
+    marker: Marker,
+    graph_type: GraphType
+}
+
+impl Dataset {
+    pub fn new(name: &str, points: &[(f64, f64)]) -> Self {
+        Dataset { name: name.to_string(), points: points.to_vec(), marker: Marker::Braille, graph_type: GraphType::Line }
+    }
+
+    pub fn marker(mut self, marker: Marker) -> Self {
+        self.marker = marker;
+        self
+    }
+
+    pub fn graph_type(mut self, graph_type: GraphType) -> Self {
+        self.graph_type = graph_type;
+        self
+    }
+}
+
+// Dots on a grid finer than the cells it's drawn on
+struct Surface {
+    width: usize,
+    height: usize,
+    dots: Vec<bool>
+}
+
+impl Surface {
+    fn new(width: usize, height: usize) -> Self {
+        Surface { width, height, dots: vec![false; width * height] }
+    }
+
+    fn set(&mut self, x: i64, y: i64) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 { return; }
+
+        self.dots[y as usize * self.width + x as usize] = true;
+    }
+
+    fn line(&mut self, from: (i64, i64), to: (i64, i64)) {
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let step_x = if x < to.0 { 1 } else { -1 };
+        let step_y = if y < to.1 { 1 } else { -1 };
+        let mut error = dx + dy;
+
+        loop {
+            self.set(x, y);
+            if (x, y) == to { break; }
+
+            let doubled = 2 * error;
+            if doubled >= dy { error += dy; x += step_x; }
+            if doubled <= dx { error += dx; y += step_y; }
+        }
+    }
+
+    // Cuts a line down to the part over the surface (Liang–Barsky), so far off points
+    // don't leave Bresenham walking across dots that are never drawn
+    fn clip(&self, from: (f64, f64), to: (f64, f64)) -> Option<((f64, f64), (f64, f64))> {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let (max_x, max_y) = ((self.width - 1) as f64, (self.height - 1) as f64);
+        let (mut enter, mut exit) = (0.0_f64, 1.0_f64);
+
+        for (p, q) in [(-dx, from.0), (dx, max_x - from.0), (-dy, from.1), (dy, max_y - from.1)] {
+            if p == 0.0 {
+                // Parallel to this edge, and outside of it
+                if q < 0.0 { return None; }
+                continue;
+            }
+
+            let t = q / p;
+            if p < 0.0 { enter = enter.max(t); } else { exit = exit.min(t); }
+            if enter > exit { return None; }
+        }
+
+        let point = |t: f64| (from.0 + t * dx, from.1 + t * dy);
+        let (start, end) = (point(enter), point(exit));
+
+        [start.0, start.1, end.0, end.1].iter().all(|v| v.is_finite()).then_some((start, end))
+    }
+
+    fn draw(&self, canvas: &mut TextCanvas, marker: &Marker, origin: (usize, usize)) {
+        let (across, down) = marker.resolution();
+
+        for cell_y in 0..self.height / down {
+            for cell_x in 0..self.width / across {
+                let dots = (0..down)
+                    .map(|y| (0..across).map(|x| self.dots[(cell_y * down + y) * self.width + cell_x * across + x]).collect())
+                    .collect::<Vec<Vec<bool>>>();
+
+                if let Some(glyph) = marker.glyph(&dots) {
+                    canvas.write(&glyph, origin.0 + cell_x, origin.1 + cell_y);
+                }
+            }
+        }
+    }
+}
+
+fn format_tick(value: f64, range: f64) -> String {
+    let text = match range {
+        r if r >= 10.0 => format!("{value:.0}"),
+        r if r >= 1.0 => format!("{value:.1}"),
+        _ => format!("{value:.2}")
+    };
+
+    // Rounding something like -0.0001 shouldn't leave a sign behind
+    match text.strip_prefix('-') {
+        Some(rest) if rest.chars().all(|c| c == '0' || c == '.') => rest.to_string(),
+        _ => text
+    }
+}
+
+// Evenly spaced values from `min` to `max`, both included
+fn ticks((min, max): (f64, f64), count: usize) -> Vec<f64> {
+    match count {
+        0 => vec![],
+        1 => vec![min],
+        _ => (0..count).map(|i| min + (max - min) * i as f64 / (count - 1) as f64).collect()
+    }
+}
+
+// Where tick `i` of `count` goes along an axis `length` cells long
+fn tick_position(i: usize, count: usize, length: usize) -> usize {
+    if count < 2 { 0 } else { i * length.saturating_sub(1) / (count - 1) }
+}
+
+fn write_text(canvas: &mut TextCanvas, text: &str, x: usize, y: usize) {
+    for (i, grapheme) in text.graphemes(true).enumerate() {
+        canvas.write(grapheme, x + i, y);
+    }
+}
+
+// Plots datasets against a y axis on the left and an x axis at the bottom
+pub struct Chart {
+    datasets: Vec<Dataset>,
+    x_bounds: Option<(f64, f64)>,
+    y_bounds: Option<(f64, f64)>,
+    x_ticks: usize,
+    y_ticks: usize,
+    legend: bool
+}
+
+impl Chart {
+    pub fn new(datasets: Vec<Dataset>) -> Self {
+        Chart { datasets, x_bounds: None, y_bounds: None, x_ticks: 3, y_ticks: 3, legend: true }
+    }
+
+    // Fixed bounds. Without them, the axis spans the data.
+    pub fn x_bounds(mut self, min: f64, max: f64) -> Self {
+        self.x_bounds = Some((min, max));
+        self
+    }
+
+    pub fn y_bounds(mut self, min: f64, max: f64) -> Self {
+        self.y_bounds = Some((min, max));
+        self
+    }
+
+    // How many labels each axis gets, the first and last at its ends
+    pub fn x_ticks(mut self, count: usize) -> Self {
+        self.x_ticks = count;
+        self
+    }
+
+    pub fn y_ticks(mut self, count: usize) -> Self {
+        self.y_ticks = count;
+        self
+    }
+
+    pub fn hide_legend(mut self) -> Self {
+        self.legend = false;
+        self
+    }
+
+    fn bounds(&self, fixed: Option<(f64, f64)>, value: fn(&(f64, f64)) -> f64) -> (f64, f64) {
+        let (min, max) = match fixed {
+            Some(bounds) => bounds,
+            None => self.datasets.iter()
+                .flat_map(|d| d.points.iter().map(value))
+                .filter(|v| v.is_finite())
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| (min.min(v), max.max(v)))
+        };
+
+        match (min, max) {
+            // No data to span
+            (min, max) if min > max && fixed.is_none() => (0.0, 1.0),
+            // A flat line still needs an axis to sit on
+            (min, max) if min == max => (min - 1.0, max + 1.0),
+            bounds => bounds
+        }
+    }
+
+    fn draw_dataset(&self, canvas: &mut TextCanvas, dataset: &Dataset, plot: &Rect, x_bounds: (f64, f64), y_bounds: (f64, f64)) {
+        let (across, down) = dataset.marker.resolution();
+        let mut surface = Surface::new(plot.width * across, plot.height * down);
+
+        let to_dot = |(x, y): (f64, f64)| {
+            let dot_x = (x - x_bounds.0) / (x_bounds.1 - x_bounds.0) * (surface.width - 1) as f64;
+            let dot_y = (y_bounds.1 - y) / (y_bounds.1 - y_bounds.0) * (surface.height - 1) as f64;
+
+            (dot_x, dot_y)
+        };
+        let round = |(x, y): (f64, f64)| (x.round() as i64, y.round() as i64);
+
+        // Points that can't be placed, like NaN, are left out
+        let dots = dataset.points.iter()
+            .map(|p| to_dot(*p))
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .collect::<Vec<_>>();
+
+        match dataset.graph_type {
+            GraphType::Scatter => dots.iter().for_each(|dot| {
+                let (x, y) = round(*dot);
+                surface.set(x, y);
+            }),
+            GraphType::Line => {
+                for pair in dots.windows(2) {
+                    if let Some((from, to)) = surface.clip(pair[0], pair[1]) {
+                        surface.line(round(from), round(to));
+                    }
+                }
+                if let [dot] = dots[..] {
+                    let (x, y) = round(dot);
+                    surface.set(x, y);
+                }
+            }
+        }
+
+        surface.draw(canvas, &dataset.marker, (plot.x as usize, plot.y as usize));
+    }
+
+    fn draw_legend(&self, canvas: &mut TextCanvas, plot: &Rect) {
+        let entries = self.datasets.iter()
+            .filter(|d| !d.name.is_empty())
+            .map(|d| format!("{} {}", d.marker.symbol(), d.name))
+            .collect::<Vec<_>>();
+
+        let width = entries.iter().map(|e| e.graphemes(true).count()).max().unwrap_or(0);
+        if width == 0 || width > plot.width { return; }
+
+        // In the top right corner, clearing the plot behind it
+        let x = plot.x as usize + plot.width - width;
+        for (y, entry) in entries.iter().take(plot.height).enumerate() {
+            write_text(canvas, &" ".repeat(width), x, plot.y as usize + y);
+            write_text(canvas, entry, x, plot.y as usize + y);
+        }
+    }
+}
+
+impl<Ctx> Widget<Ctx> for Chart {
+    fn sizing(&self, _bounds: &Rect, _context: &mut Ctx) -> ItemSizing {
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Greedy(1))
+    }
+
+    fn render(&self, size: &Size, _context: &mut Ctx) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+
+        let x_bounds = self.bounds(self.x_bounds, |p| p.0);
+        let y_bounds = self.bounds(self.y_bounds, |p| p.1);
+
+        let y_range = y_bounds.1 - y_bounds.0;
+        let y_labels = ticks(y_bounds, self.y_ticks).into_iter().map(|v| format_tick(v, y_range)).collect::<Vec<_>>();
+        let axis_x = y_labels.iter().map(|l| l.graphemes(true).count()).max().unwrap_or(0);
+
+        // Room for the x axis and its labels below the plot, and the y axis on its left
+        if size.width < axis_x + 2 || size.height < 3 { return canvas; }
+        let plot = Rect::new(axis_x as i64 + 1, 0, size.width - axis_x - 1, size.height - 2);
+        let axis_y = plot.height;
+
+        canvas.draw_rect(&Rect::new(axis_x as i64, 0, 1, plot.height), "│");
+        canvas.draw_rect(&Rect::new(plot.x, axis_y as i64, plot.width, 1), "─");
+        canvas.write("└", axis_x, axis_y);
+
+        for (i, label) in y_labels.iter().enumerate() {
+            let y = axis_y - 1 - tick_position(i, y_labels.len(), plot.height);
+
+            write_text(&mut canvas, label, axis_x - label.graphemes(true).count(), y);
+            canvas.write("┤", axis_x, y);
+        }
+
+        let x_range = x_bounds.1 - x_bounds.0;
+        let x_ticks = ticks(x_bounds, self.x_ticks);
+
+        for (i, value) in x_ticks.iter().enumerate() {
+            let label = format_tick(*value, x_range);
+            let label_width = label.graphemes(true).count();
+            let x = plot.x as usize + tick_position(i, x_ticks.len(), plot.width);
+
+            // Centred under the tick, but kept inside the chart
+            let label_x = x.saturating_sub(label_width / 2).min(size.width.saturating_sub(label_width));
+
+            canvas.write("┴", x, axis_y);
+            write_text(&mut canvas, &label, label_x, axis_y + 1);
+        }
+
+        for dataset in &self.datasets {
+            self.draw_dataset(&mut canvas, dataset, &plot, x_bounds, y_bounds);
+        }
+
+        if self.legend {
+            self.draw_legend(&mut canvas, &plot);
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(chart: &Chart, width: usize, height: usize) -> Vec<String> {
+        Widget::<()>::render(chart, &Size::new(width, height), &mut ()).to_string().lines()
+            .map(|line| line.trim_end().to_string())
+            .collect()
+    }
+
+    fn plot(points: &[(f64, f64)]) -> Chart {
+        Chart::new(vec![Dataset::new("", points).marker(Marker::Char('•'))])
+            .x_bounds(0.0, 4.0)
+            .y_bounds(0.0, 2.0)
+            .x_ticks(0)
+            .y_ticks(0)
+    }
+
+    #[test]
+    fn lines_are_clipped_to_the_plot() {
+        let chart = plot(&[(-1e12, 1.0), (1e12, 1.0)]);
+
+        assert_eq!(rendered(&chart, 6, 5), vec![
+            "│",
+            "│•••••",
+            "│",
+            "└─────",
+            ""
+        ]);
+    }
+
+    #[test]
+    fn lines_outside_the_plot_are_not_drawn() {
+        let chart = plot(&[(-1e300, 5.0), (1e300, 5.0), (2.0, f64::MAX)]);
+
+        assert_eq!(rendered(&chart, 6, 5), vec![
+            "│",
+            "│",
+            "│",
+            "└─────",
+            ""
+        ]);
+    }
+
+    #[test]
+    fn points_that_are_not_finite_are_skipped() {
+        let chart = plot(&[(0.0, 0.0), (f64::NAN, 1.0), (4.0, 0.0), (2.0, f64::INFINITY)]);
+
+        assert_eq!(rendered(&chart, 6, 5), vec![
+            "│",
+            "│",
+            "│•••••",
+            "└─────",
+            ""
+        ]);
+
+        let bounds = Chart::new(vec![Dataset::new("", &[(f64::NAN, 1.0), (2.0, 3.0), (4.0, f64::NEG_INFINITY)])]);
+        assert_eq!(bounds.bounds(None, |p| p.0), (2.0, 4.0));
+        assert_eq!(bounds.bounds(None, |p| p.1), (1.0, 3.0));
+    }
+
+    #[test]
+    fn equal_bounds_are_padded() {
+        let chart = Chart::new(vec![Dataset::new("", &[(1.0, 2.0)])]).x_bounds(3.0, 3.0);
+
+        assert_eq!(chart.bounds(chart.x_bounds, |p| p.0), (2.0, 4.0));
+        assert_eq!(chart.bounds(chart.y_bounds, |p| p.1), (1.0, 3.0));
+        assert_eq!(Chart::new(vec![]).bounds(None, |p| p.0), (0.0, 1.0));
+    }
+}
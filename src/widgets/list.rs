@@ -0,0 +1,268 @@
+use std::collections::BTreeSet;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{animation::{AnimationEvent, KeyCode, KeyModifiers}, canvas::TextCanvas, layout::{geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}}};
+
+use super::Widget;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListState {
+    selected: Option<usize>,
+    // The first item in view
+    offset: usize,
+    marked: BTreeSet<usize>,
+    // Whether Space marks items, as the widget was last rendered
+    multi_select: bool,
+    // What it was last rendered with, for clamping and moving by pages
+    len: usize,
+    height: usize
+}
+
+impl ListState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selected = index;
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    // The number of items, for keys to move the selection before the first render.
+    // Rendering keeps it up to date after that.
+    pub fn set_len(&mut self, len: usize) {
+        self.len = len;
+        self.selected = self.selected.filter(|_| len > 0).map(|i| i.min(len - 1));
+        self.marked.retain(|i| *i < len);
+    }
+
+    pub fn marked(&self) -> Vec<usize> {
+        self.marked.iter().copied().collect()
+    }
+
+    pub fn is_marked(&self, index: usize) -> bool {
+        self.marked.contains(&index)
+    }
+
+    // Marks or unmarks the selected item
+    pub fn toggle_mark(&mut self) {
+        let Some(selected) = self.selected else { return };
+
+        if !self.marked.remove(&selected) {
+            self.marked.insert(selected);
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    pub fn handle_event<Msg>(&mut self, event: &AnimationEvent<Msg>) -> bool {
+        match event {
+            AnimationEvent::KeyEvent(code, modifiers) => self.handle_key(*code, *modifiers),
+            _ => false
+        }
+    }
+
+    // Space marks the selected item when the list was rendered with marks. Returns true when
+    // the key was used.
+    pub fn handle_key(&mut self, code: KeyCode, _modifiers: KeyModifiers) -> bool {
+        if self.len == 0 { return false; }
+
+        let last = self.len - 1;
+        let page = self.height.max(1);
+
+        let selected = match (code, self.selected) {
+            (KeyCode::Up, None) => last,
+            (KeyCode::Up, Some(i)) => i.saturating_sub(1),
+            (KeyCode::Down, None) => 0,
+            (KeyCode::Down, Some(i)) => (i + 1).min(last),
+            (KeyCode::PageUp, i) => i.unwrap_or(0).saturating_sub(page),
+            (KeyCode::PageDown, i) => i.map_or(0, |i| i + page).min(last),
+            (KeyCode::Home, _) => 0,
+            (KeyCode::End, _) => last,
+            (KeyCode::Char(' '), _) if self.multi_select => {
+                self.toggle_mark();
+                return true;
+            }
+            _ => return false
+        };
+
+        self.selected = Some(selected);
+
+        true
+    }
+
+    // Moves the view just enough for the selection to be in it
+    pub(crate) fn scroll_to_selection(&mut self, len: usize, height: usize) {
+        self.set_len(len);
+        self.height = height;
+
+        if let Some(selected) = self.selected {
+            if selected < self.offset {
+                self.offset = selected;
+            } else if selected >= self.offset + height {
+                self.offset = selected + 1 - height;
+            }
+        }
+
+        self.offset = self.offset.min(len.saturating_sub(height));
+    }
+}
+
+// Rows of text over a `ListState` in the context. Only the rows in view are drawn.
+pub struct List<Ctx> {
+    items: Vec<String>,
+    state: fn(&mut Ctx) -> &mut ListState,
+    highlight_symbol: String,
+    highlight_style: Option<fn(&str) -> String>,
+    marks: Option<(String, String)>
+}
+
+impl<Ctx> List<Ctx> {
+    pub fn new(items: Vec<String>, state: fn(&mut Ctx) -> &mut ListState) -> Self {
+        List { items, state, highlight_symbol: "> ".to_string(), highlight_style: None, marks: None }
+    }
+
+    // Drawn before the selected item, with every other item indented as much
+    pub fn highlight_symbol(mut self, symbol: &str) -> Self {
+        self.highlight_symbol = symbol.to_string();
+        self
+    }
+
+    // Rewrites the selected item, e.g. to upper case it or wrap it in brackets
+    pub fn highlight_style(mut self, style: fn(&str) -> String) -> Self {
+        self.highlight_style = Some(style);
+        self
+    }
+
+    // Shows a "[x] " or "[ ] " column for marked items, which Space toggles
+    pub fn multi_select(self) -> Self {
+        self.marks("[x] ", "[ ] ")
+    }
+
+    pub fn marks(mut self, marked: &str, unmarked: &str) -> Self {
+        self.marks = Some((marked.to_string(), unmarked.to_string()));
+        self
+    }
+}
+
+impl<Ctx> Widget<Ctx> for List<Ctx> {
+    fn sizing(&self, _bounds: &Rect, _context: &mut Ctx) -> ItemSizing {
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Greedy(1))
+    }
+
+    fn render(&self, size: &Size, context: &mut Ctx) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+
+        let state = (self.state)(context);
+        state.scroll_to_selection(self.items.len(), size.height);
+        state.multi_select = self.marks.is_some();
+
+        let indent = " ".repeat(self.highlight_symbol.graphemes(true).count());
+
+        for (y, (index, item)) in self.items.iter().enumerate().skip(state.offset).take(size.height).enumerate() {
+            let is_selected = state.selected == Some(index);
+
+            let mut row = if is_selected { self.highlight_symbol.clone() } else { indent.clone() };
+
+            if let Some((marked, unmarked)) = &self.marks {
+                row.push_str(if state.is_marked(index) { marked } else { unmarked });
+            }
+
+            match self.highlight_style {
+                Some(style) if is_selected => row.push_str(&style(item)),
+                _ => row.push_str(item)
+            }
+
+            for (x, grapheme) in row.graphemes(true).take(size.width).enumerate() {
+                canvas.write(grapheme, x, y);
+            }
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[&str]) -> List<ListState> {
+        List::new(items.iter().map(|item| item.to_string()).collect(), |state: &mut ListState| state)
+    }
+
+    fn press(state: &mut ListState, code: KeyCode) -> bool {
+        state.handle_key(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn keys_move_the_selection_before_the_first_render_once_the_length_is_known() {
+        let mut state = ListState::new();
+        assert!(!press(&mut state, KeyCode::Down));
+
+        state.set_len(3);
+        assert!(press(&mut state, KeyCode::Down));
+        assert_eq!(state.selected(), Some(0));
+
+        press(&mut state, KeyCode::End);
+        press(&mut state, KeyCode::Down);
+        assert_eq!(state.selected(), Some(2));
+
+        state.set_len(2);
+        assert_eq!(state.selected(), Some(1));
+    }
+
+    #[test]
+    fn space_only_marks_items_with_multi_select() {
+        let mut state = ListState::new();
+        state.select(Some(1));
+
+        list(&["a", "b"]).render(&Size::new(10, 2), &mut state);
+        assert!(!press(&mut state, KeyCode::Char(' ')));
+        assert!(state.marked().is_empty());
+
+        let canvas = list(&["a", "b"]).multi_select().render(&Size::new(10, 2), &mut state);
+        assert!(canvas.to_string().contains("[ ] b"));
+
+        assert!(press(&mut state, KeyCode::Char(' ')));
+        assert_eq!(state.marked(), vec![1]);
+    }
+
+    #[test]
+    fn pages_move_by_the_rendered_height() {
+        let mut state = ListState::new();
+        list(&["a", "b", "c", "d", "e", "f"]).render(&Size::new(5, 2), &mut state);
+
+        press(&mut state, KeyCode::PageDown);
+        assert_eq!(state.selected(), Some(0));
+
+        press(&mut state, KeyCode::PageDown);
+        assert_eq!(state.selected(), Some(2));
+
+        list(&["a", "b", "c", "d", "e", "f"]).render(&Size::new(5, 2), &mut state);
+        assert_eq!(state.offset(), 1);
+
+        press(&mut state, KeyCode::PageUp);
+        assert_eq!(state.selected(), Some(0));
+    }
+
+    #[test]
+    fn only_the_rows_in_view_are_drawn() {
+        let mut state = ListState::new();
+        state.select(Some(3));
+
+        let canvas = list(&["a", "b", "c", "d"]).highlight_symbol("* ").render(&Size::new(4, 2), &mut state);
+        let lines: Vec<_> = canvas.to_string().lines().map(|line| line.trim_end().to_string()).collect();
+
+        assert_eq!(lines, vec!["  c", "* d"]);
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{canvas::TextCanvas, layout::{geometry::{Rect, Size}, sizing::ItemSizing}};
+
+pub mod text_input;
+pub mod text_area;
+pub mod list;
+pub mod table;
+pub mod progress;
+pub mod spinner;
+pub mod sparkline;
+pub mod bar_chart;
+pub mod chart;
+pub mod tree;
+pub mod tabs;
+
+// A leaf of the layout that sizes and draws itself. Widgets that keep state across frames
+// reach it in the context through a lens, so rendering can keep things like the scroll
+// position up to date.
+pub trait Widget<Ctx> {
+    fn sizing(&self, bounds: &Rect, context: &mut Ctx) -> ItemSizing;
+
+    // Draws into a canvas of the size the layout settled on
+    fn render(&self, size: &Size, context: &mut Ctx) -> TextCanvas;
+}
+
+// Swaps the colours a grapheme is drawn with, which is how selections are shown by default
+pub fn inverted(grapheme: &str) -> String {
+    format!("\x1b[7m{grapheme}\x1b[27m")
+}
@@ -0,0 +1,256 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{canvas::TextCanvas, layout::{geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}}};
+
+use super::Widget;
+
+// Blocks filling one to seven eighths of a cell, from the left and from the bottom
+pub(crate) const LEFT_EIGHTHS: [&str; 7] = ["▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+pub(crate) const LOWER_EIGHTHS: [&str; 7] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇"];
+const FULL_BLOCK: &str = "█";
+
+// How long an indeterminate bar takes to go across and back
+const INDETERMINATE_PERIOD_MILIS: f64 = 2000.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LabelPlacement {
+    // Takes space before the bar
+    Left,
+    // Takes space after the bar
+    Right,
+    // Drawn over the middle of the bar
+    Center
+}
+
+// The glyph for each of `cells` cells when `ratio` of them are filled, in eighths
+pub(crate) fn fill(ratio: f64, cells: usize, eighths: &[&'static str; 7]) -> Vec<&'static str> {
+    let filled = (ratio.clamp(0.0, 1.0) * (cells * 8) as f64).round() as usize;
+
+    (0..cells)
+        .map(|cell| match filled.saturating_sub(cell * 8) {
+            0 => " ",
+            n if n >= 8 => FULL_BLOCK,
+            n => eighths[n - 1]
+        })
+        .collect()
+}
+
+fn write_centered(canvas: &mut TextCanvas, text: &str, x: usize, width: usize, y: usize) {
+    let graphemes = text.graphemes(true).take(width).collect::<Vec<_>>();
+    let start = x + (width - graphemes.len()) / 2;
+
+    for (i, grapheme) in graphemes.into_iter().enumerate() {
+        canvas.write(grapheme, start + i, y);
+    }
+}
+
+// A one row bar. Owns what it shows, so it also renders outside of an animation.
+pub struct ProgressBar {
+    ratio: f64,
+    label: Option<String>,
+    placement: LabelPlacement,
+    // Where an indeterminate bar is in its sweep
+    indeterminate: Option<f64>
+}
+
+impl ProgressBar {
+    // `ratio` goes from 0 to 1
+    pub fn new(ratio: f64) -> Self {
+        ProgressBar { ratio: ratio.clamp(0.0, 1.0), label: None, placement: LabelPlacement::Right, indeterminate: None }
+    }
+
+    pub fn from_fraction(done: usize, total: usize) -> Self {
+        Self::new(if total == 0 { 0.0 } else { done as f64 / total as f64 })
+    }
+
+    // For work of unknown length: a block sweeping back and forth, given the elapsed time
+    pub fn indeterminate(elapsed_milis: f64) -> Self {
+        let mut bar = Self::new(0.0);
+        bar.indeterminate = Some(elapsed_milis);
+
+        bar
+    }
+
+    pub fn label(mut self, text: &str) -> Self {
+        self.label = Some(text.to_string());
+        self
+    }
+
+    pub fn percentage(self) -> Self {
+        let text = format!("{:.0}%", self.ratio * 100.0);
+        self.label(&text)
+    }
+
+    pub fn label_placement(mut self, placement: LabelPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    fn bar(&self, width: usize) -> Vec<&'static str> {
+        let Some(elapsed) = self.indeterminate else { return fill(self.ratio, width, &LEFT_EIGHTHS) };
+
+        let block = (width / 4).max(1);
+        let travel = width - block.min(width);
+
+        let phase = (elapsed / INDETERMINATE_PERIOD_MILIS).fract();
+        let sweep = if phase < 0.5 { phase * 2.0 } else { (1.0 - phase) * 2.0 };
+        let start = (sweep * travel as f64).round() as usize;
+
+        (0..width)
+            .map(|x| if x >= start && x < start + block { FULL_BLOCK } else { " " })
+            .collect()
+    }
+}
+
+impl<Ctx> Widget<Ctx> for ProgressBar {
+    fn sizing(&self, _bounds: &Rect, _context: &mut Ctx) -> ItemSizing {
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Static(1))
+    }
+
+    fn render(&self, size: &Size, _context: &mut Ctx) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+        if size.height == 0 { return canvas; }
+
+        let label = self.label.as_deref().unwrap_or("");
+        let label_width = label.graphemes(true).count().min(size.width);
+        let beside = if label.is_empty() || self.placement == LabelPlacement::Center { 0 } else { label_width + 1 };
+
+        let bar_width = size.width.saturating_sub(beside);
+        let bar_x = if self.placement == LabelPlacement::Left { beside } else { 0 };
+
+        for (i, glyph) in self.bar(bar_width).into_iter().enumerate() {
+            canvas.write(glyph, bar_x + i, 0);
+        }
+
+        match self.placement {
+            _ if label.is_empty() => {}
+            LabelPlacement::Left => write_centered(&mut canvas, label, 0, label_width, 0),
+            LabelPlacement::Right => write_centered(&mut canvas, label, size.width - label_width, label_width, 0),
+            LabelPlacement::Center => write_centered(&mut canvas, label, 0, size.width, 0)
+        }
+
+        canvas
+    }
+}
+
+// Fills the whole area it's given, left to right or bottom to top, with the label in the middle
+pub struct Gauge {
+    ratio: f64,
+    label: Option<String>,
+    vertical: bool
+}
+
+impl Gauge {
+    // `ratio` goes from 0 to 1
+    pub fn new(ratio: f64) -> Self {
+        Gauge { ratio: ratio.clamp(0.0, 1.0), label: None, vertical: false }
+    }
+
+    pub fn label(mut self, text: &str) -> Self {
+        self.label = Some(text.to_string());
+        self
+    }
+
+    pub fn percentage(self) -> Self {
+        let text = format!("{:.0}%", self.ratio * 100.0);
+        self.label(&text)
+    }
+
+    // Fills from the bottom up
+    pub fn vertical(mut self) -> Self {
+        self.vertical = true;
+        self
+    }
+}
+
+impl<Ctx> Widget<Ctx> for Gauge {
+    fn sizing(&self, _bounds: &Rect, _context: &mut Ctx) -> ItemSizing {
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Greedy(1))
+    }
+
+    fn render(&self, size: &Size, _context: &mut Ctx) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+        if size.height == 0 { return canvas; }
+
+        if self.vertical {
+            let column = fill(self.ratio, size.height, &LOWER_EIGHTHS);
+
+            for (i, glyph) in column.into_iter().enumerate() {
+                canvas.draw_rect(&Rect::new(0, (size.height - 1 - i) as i64, size.width, 1), glyph);
+            }
+        } else {
+            let row = fill(self.ratio, size.width, &LEFT_EIGHTHS);
+
+            for (x, glyph) in row.into_iter().enumerate() {
+                canvas.draw_rect(&Rect::new(x as i64, 0, 1, size.height), glyph);
+            }
+        }
+
+        if let Some(label) = &self.label {
+            write_centered(&mut canvas, label, 0, size.width, size.height / 2);
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(widget: &impl Widget<()>, width: usize, height: usize) -> Vec<String> {
+        widget.render(&Size::new(width, height), &mut ()).to_string().lines()
+            .map(|line| line.trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn fill_rounds_to_the_nearest_eighth() {
+        assert_eq!(fill(0.5, 3, &LEFT_EIGHTHS), vec!["█", "▌", " "]);
+        assert_eq!(fill(0.3, 1, &LEFT_EIGHTHS), vec!["▎"]);
+        assert_eq!(fill(0.99, 2, &LEFT_EIGHTHS), vec!["█", "█"]);
+        assert_eq!(fill(0.01, 2, &LEFT_EIGHTHS), vec![" ", " "]);
+        assert_eq!(fill(1.5, 2, &LOWER_EIGHTHS), vec!["█", "█"]);
+    }
+
+    #[test]
+    fn labels_go_beside_or_over_the_bar() {
+        let bar = || ProgressBar::new(0.5).percentage();
+
+        assert_eq!(rendered(&bar(), 10, 1), vec!["███    50%"]);
+        assert_eq!(rendered(&bar().label_placement(LabelPlacement::Left), 10, 1), vec!["50% ███"]);
+        assert_eq!(rendered(&bar().label_placement(LabelPlacement::Center), 10, 1), vec!["███50%"]);
+    }
+
+    #[test]
+    fn a_label_wider_than_the_bar_is_cut() {
+        for placement in [LabelPlacement::Left, LabelPlacement::Right, LabelPlacement::Center] {
+            let bar = ProgressBar::new(1.0).label("loading").label_placement(placement.clone());
+
+            assert_eq!(rendered(&bar, 4, 1), vec!["load"], "{placement:?}");
+        }
+    }
+
+    #[test]
+    fn nothing_of_nothing_is_an_empty_bar() {
+        assert_eq!(rendered(&ProgressBar::from_fraction(3, 0), 4, 1), vec![""]);
+        assert_eq!(rendered(&ProgressBar::from_fraction(1, 2), 4, 1), vec!["██"]);
+    }
+
+    #[test]
+    fn an_indeterminate_bar_sweeps_across_and_back() {
+        let at = |elapsed| rendered(&ProgressBar::indeterminate(elapsed), 8, 1);
+
+        assert_eq!(at(0.0), vec!["██"]);
+        assert_eq!(at(500.0), vec!["   ██"]);
+        assert_eq!(at(1000.0), vec!["      ██"]);
+        assert_eq!(at(1500.0), vec!["   ██"]);
+        assert_eq!(at(2000.0), vec!["██"]);
+    }
+
+    #[test]
+    fn a_vertical_gauge_fills_from_the_bottom() {
+        assert_eq!(rendered(&Gauge::new(0.5).vertical(), 3, 3), vec!["", "▄▄▄", "███"]);
+        assert_eq!(rendered(&Gauge::new(0.5).vertical().percentage(), 3, 3), vec!["", "50%", "███"]);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{canvas::TextCanvas, layout::{geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}}};
+
+use super::{progress::{fill, LOWER_EIGHTHS}, Widget};
+
+// One column per value, the latest on the right, scaled to the height it's given
+pub struct Sparkline {
+    data: Vec<f64>,
+    max: Option<f64>
+}
+
+impl Sparkline {
+    pub fn new(data: &[f64]) -> Self {
+        Sparkline { data: data.to_vec(), max: None }
+    }
+
+    // The value of a full column. Defaults to the largest value.
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+}
+
+impl<Ctx> Widget<Ctx> for Sparkline {
+    fn sizing(&self, _bounds: &Rect, _context: &mut Ctx) -> ItemSizing {
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Greedy(1))
+    }
+
+    fn render(&self, size: &Size, _context: &mut Ctx) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+
+        let max = self.max.unwrap_or_else(|| self.data.iter().copied().fold(0.0, f64::max));
+        if max <= 0.0 { return canvas; }
+
+        let shown = self.data.len().min(size.width);
+        let start = size.width - shown;
+
+        for (i, value) in self.data[self.data.len() - shown..].iter().enumerate() {
+            for (row, glyph) in fill(value / max, size.height, &LOWER_EIGHTHS).into_iter().enumerate() {
+                canvas.write(glyph, start + i, size.height - 1 - row);
+            }
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(sparkline: &Sparkline, width: usize, height: usize) -> Vec<String> {
+        Widget::<()>::render(sparkline, &Size::new(width, height), &mut ()).to_string().lines()
+            .map(|line| line.trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn the_newest_values_are_kept_on_the_right() {
+        assert_eq!(rendered(&Sparkline::new(&[1.0, 2.0, 3.0, 4.0]), 3, 1), vec!["▄▆█"]);
+        assert_eq!(rendered(&Sparkline::new(&[4.0]), 3, 1), vec!["  █"]);
+        assert_eq!(rendered(&Sparkline::new(&[2.0, 4.0]), 2, 2), vec![" █", "██"]);
+    }
+
+    #[test]
+    fn values_over_the_max_are_clamped() {
+        assert_eq!(rendered(&Sparkline::new(&[1.0, 4.0]).max(2.0), 2, 2), vec![" █", "██"]);
+        assert_eq!(rendered(&Sparkline::new(&[1.0, 4.0]).max(2.0), 2, 1), vec!["▄█"]);
+    }
+
+    #[test]
+    fn nothing_is_drawn_without_values() {
+        assert_eq!(rendered(&Sparkline::new(&[0.0, 0.0]), 3, 2), vec!["", ""]);
+        assert_eq!(rendered(&Sparkline::new(&[]), 3, 2), vec!["", ""]);
+        assert_eq!(rendered(&Sparkline::new(&[1.0]).max(0.0), 3, 1), vec![""]);
+    }
+}
@@ -0,0 +1,197 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{animation::{AnimationContext, AnimationMessage, AnimationState}, canvas::TextCanvas, layout::{geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}}};
+
+use super::Widget;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpinnerFrames {
+    Dots,
+    Line,
+    Braille,
+    Arrows,
+    Custom(Vec<String>)
+}
+
+impl SpinnerFrames {
+    fn frames(&self) -> Vec<String> {
+        let frames: &[&str] = match self {
+            SpinnerFrames::Dots => &[".  ", ".. ", "...", " ..", "  .", "   "],
+            SpinnerFrames::Line => &["-", "\\", "|", "/"],
+            SpinnerFrames::Braille => &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+            SpinnerFrames::Arrows => &["←", "↖", "↑", "↗", "→", "↘", "↓", "↙"],
+            SpinnerFrames::Custom(frames) => return frames.clone()
+        };
+
+        frames.iter().map(|f| f.to_string()).collect()
+    }
+
+    fn default_interval_milis(&self) -> f64 {
+        match self {
+            SpinnerFrames::Dots => 200.0,
+            SpinnerFrames::Line => 130.0,
+            SpinnerFrames::Braille => 80.0,
+            SpinnerFrames::Arrows => 100.0,
+            SpinnerFrames::Custom(_) => 100.0
+        }
+    }
+}
+
+// Picks its frame from the time elapsed in the animation, so it spins at the same speed
+// however fast the loop runs. Outside of an animation it shows the first frame.
+pub struct Spinner {
+    frames: Vec<String>,
+    interval_milis: f64,
+    label: Option<String>,
+    elapsed_milis: Option<f64>
+}
+
+impl Spinner {
+    pub fn new(frames: SpinnerFrames) -> Self {
+        Spinner {
+            interval_milis: frames.default_interval_milis(),
+            frames: frames.frames(),
+            label: None,
+            elapsed_milis: None
+        }
+    }
+
+    // How long each frame is shown
+    pub fn interval(mut self, milis: f64) -> Self {
+        self.interval_milis = milis;
+        self
+    }
+
+    // Drawn after the spinner
+    pub fn label(mut self, text: &str) -> Self {
+        self.label = Some(text.to_string());
+        self
+    }
+
+    // Overrides the animation's elapsed time, e.g. to start spinning from when a task started
+    pub fn elapsed(mut self, milis: f64) -> Self {
+        self.elapsed_milis = Some(milis);
+        self
+    }
+
+    fn frame_width(&self) -> usize {
+        self.frames.iter()
+            .map(|f| f.graphemes(true).count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn text(&self, elapsed_milis: f64) -> String {
+        let elapsed_milis = self.elapsed_milis.unwrap_or(elapsed_milis);
+
+        let frame = match self.frames.len() {
+            0 => String::new(),
+            len => {
+                let index = (elapsed_milis.max(0.0) / self.interval_milis.max(1.0)) as usize % len;
+                self.frames[index].clone()
+            }
+        };
+
+        match &self.label {
+            Some(label) => format!("{frame:<width$} {label}", width = self.frame_width()),
+            None => frame
+        }
+    }
+
+    fn item_sizing(&self) -> ItemSizing {
+        let label_width = self.label.as_ref().map_or(0, |l| l.graphemes(true).count() + 1);
+
+        ItemSizing::new(Sizing::Static(self.frame_width() + label_width), Sizing::Static(1))
+    }
+
+    fn render_text(&self, size: &Size, elapsed_milis: f64) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+
+        for (x, grapheme) in self.text(elapsed_milis).graphemes(true).enumerate() {
+            canvas.write(grapheme, x, 0);
+        }
+
+        canvas
+    }
+}
+
+impl<State: AnimationState, Msg: AnimationMessage> Widget<AnimationContext<State, Msg>> for Spinner {
+    fn sizing(&self, _bounds: &Rect, _context: &mut AnimationContext<State, Msg>) -> ItemSizing {
+        self.item_sizing()
+    }
+
+    fn render(&self, size: &Size, context: &mut AnimationContext<State, Msg>) -> TextCanvas {
+        self.render_text(size, context.elapsed_milis)
+    }
+}
+
+impl Widget<()> for Spinner {
+    fn sizing(&self, _bounds: &Rect, _context: &mut ()) -> ItemSizing {
+        self.item_sizing()
+    }
+
+    fn render(&self, size: &Size, _context: &mut ()) -> TextCanvas {
+        self.render_text(size, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::animation::PlainAnimationContext;
+
+    use super::*;
+
+    fn shown(spinner: &Spinner, context: &mut PlainAnimationContext) -> String {
+        let area = spinner.sizing(&Rect::new(0, 0, 20, 1), context).fit_into(&Rect::new(0, 0, 20, 1));
+        let size = Size::new(area.width, area.height);
+
+        spinner.render(&size, context).to_string().lines().next().unwrap_or("").to_string()
+    }
+
+    #[test]
+    fn the_frame_follows_the_elapsed_time_not_the_frame_count() {
+        let spinner = Spinner::new(SpinnerFrames::Line);
+        let mut context = PlainAnimationContext::default();
+
+        assert_eq!(shown(&spinner, &mut context), "-");
+
+        context.frame_count = 7;
+        assert_eq!(shown(&spinner, &mut context), "-");
+
+        context.elapsed_milis = 130.0;
+        assert_eq!(shown(&spinner, &mut context), "\\");
+
+        context.frame_count = 1000;
+        context.elapsed_milis = 4.0 * 130.0 + 260.0;
+        assert_eq!(shown(&spinner, &mut context), "|");
+    }
+
+    #[test]
+    fn the_interval_sets_the_speed() {
+        let mut context = PlainAnimationContext::default();
+        context.elapsed_milis = 25.0;
+
+        assert_eq!(shown(&Spinner::new(SpinnerFrames::Line), &mut context), "-");
+        assert_eq!(shown(&Spinner::new(SpinnerFrames::Line).interval(10.0), &mut context), "|");
+    }
+
+    #[test]
+    fn the_label_stays_put_while_frames_change_width() {
+        let spinner = Spinner::new(SpinnerFrames::Custom(vec!["*".to_string(), "***".to_string()])).label("wait");
+        let mut context = PlainAnimationContext::default();
+
+        assert_eq!(shown(&spinner, &mut context), "*   wait");
+
+        context.elapsed_milis = 100.0;
+        assert_eq!(shown(&spinner, &mut context), "*** wait");
+    }
+
+    #[test]
+    fn no_frames_only_shows_the_label() {
+        let mut context = PlainAnimationContext::default();
+        context.elapsed_milis = 500.0;
+
+        assert_eq!(shown(&Spinner::new(SpinnerFrames::Custom(vec![])), &mut context), "");
+        assert_eq!(shown(&Spinner::new(SpinnerFrames::Custom(vec![])).label("wait"), &mut context), " wait");
+    }
+}
@@ -0,0 +1,295 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{canvas::TextCanvas, layout::{alignment::{Edge, HorizontalAlignment, VerticalAlignment}, geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}, Layout}};
+
+use super::{list::ListState, Widget};
+
+// Rows are selected and scrolled the same way list items are
+pub type TableState = ListState;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constraint {
+    Fixed(usize),
+    // Of the width left after separators
+    Percentage(usize),
+    // At least this wide, and shares whatever space is left over
+    Min(usize),
+    // As wide as its content, up to this
+    Max(usize),
+    FitContent
+}
+
+// Rows of cells over a `TableState` in the context. Only the rows in view are drawn.
+pub struct Table<Ctx> {
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+    state: fn(&mut Ctx) -> &mut TableState,
+    widths: Vec<Constraint>,
+    alignments: Vec<HorizontalAlignment>,
+    separator: Option<char>,
+    header_separator: Option<char>,
+    highlight_symbol: String
+}
+
+impl<Ctx> Table<Ctx> {
+    pub fn new(rows: Vec<Vec<String>>, state: fn(&mut Ctx) -> &mut TableState) -> Self {
+        Table {
+            header: None,
+            rows,
+            state,
+            widths: vec![],
+            alignments: vec![],
+            separator: None,
+            header_separator: None,
+            highlight_symbol: "> ".to_string()
+        }
+    }
+
+    // Stays in place while the rows scroll
+    pub fn header(mut self, titles: Vec<String>) -> Self {
+        self.header = Some(titles);
+        self
+    }
+
+    // Columns without a constraint fit their content
+    pub fn widths(mut self, widths: Vec<Constraint>) -> Self {
+        self.widths = widths;
+        self
+    }
+
+    // Columns without an alignment are aligned to the left
+    pub fn alignments(mut self, alignments: Vec<HorizontalAlignment>) -> Self {
+        self.alignments = alignments;
+        self
+    }
+
+    // A border between columns
+    pub fn separator(mut self, c: char) -> Self {
+        self.separator = Some(c);
+        self
+    }
+
+    // A border under the header, when there is one
+    pub fn header_separator(mut self, c: char) -> Self {
+        self.header_separator = Some(c);
+        self
+    }
+
+    // Drawn before the selected row, with every other row indented as much
+    pub fn highlight_symbol(mut self, symbol: &str) -> Self {
+        self.highlight_symbol = symbol.to_string();
+        self
+    }
+
+    fn column_count(&self) -> usize {
+        self.rows.iter()
+            .chain(self.header.iter())
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn gap(&self) -> usize {
+        if self.separator.is_some() { 3 } else { 1 }
+    }
+
+    fn content_width(&self, column: usize) -> usize {
+        self.rows.iter()
+            .chain(self.header.iter())
+            .filter_map(|row| row.get(column))
+            .map(|cell| cell.graphemes(true).count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // `Min` columns get their minimum first, then the others get what they ask for from left
+    // to right while there's room. The space left over is shared between the `Min` columns.
+    fn column_widths(&self, width: usize) -> Vec<usize> {
+        let columns = self.column_count();
+        let available = width.saturating_sub(self.gap() * columns.saturating_sub(1));
+
+        let constraints = (0..columns)
+            .map(|i| self.widths.get(i).cloned().unwrap_or(Constraint::FitContent))
+            .collect::<Vec<_>>();
+
+        let mut remaining = available;
+        let mut widths = vec![0; columns];
+
+        for (i, constraint) in constraints.iter().enumerate() {
+            if let Constraint::Min(n) = constraint {
+                widths[i] = (*n).min(remaining);
+                remaining -= widths[i];
+            }
+        }
+
+        for (i, constraint) in constraints.iter().enumerate() {
+            let wanted = match constraint {
+                Constraint::Fixed(n) => *n,
+                Constraint::Percentage(p) => available * p / 100,
+                Constraint::Min(_) => continue,
+                Constraint::Max(n) => self.content_width(i).min(*n),
+                Constraint::FitContent => self.content_width(i)
+            };
+
+            widths[i] = wanted.min(remaining);
+            remaining -= widths[i];
+        }
+
+        let growing = constraints.iter().enumerate()
+            .filter(|(_, c)| matches!(c, Constraint::Min(_)))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        for (n, i) in growing.iter().enumerate() {
+            widths[*i] += remaining / growing.len() + usize::from(n < remaining % growing.len());
+        }
+
+        widths
+    }
+
+    // The separator is a right border on every column but the last, padded like the gap without one
+    fn row_layout(&self, cells: &[String], widths: &[usize], prefix: &str) -> Layout<()> {
+        let last = widths.len().saturating_sub(1);
+
+        let mut nodes = vec![Layout::text(prefix)];
+        nodes.extend(widths.iter().enumerate().map(|(i, width)| {
+            let cell = cells.get(i).map_or("", String::as_str);
+            let alignment = self.alignments.get(i).unwrap_or(&HorizontalAlignment::Left);
+            let cell = align(cell, *width, alignment);
+
+            match self.separator {
+                _ if i == last => Layout::text(&cell),
+                Some(c) => Layout::text(&format!("{cell} ")).border(1, c, hash_set!(Edge::Right)).padding_right(1),
+                None => Layout::text(&format!("{cell} "))
+            }
+        }));
+
+        Layout::HorizontalStack(VerticalAlignment::Top, 0, nodes)
+    }
+}
+
+fn align(text: &str, width: usize, alignment: &HorizontalAlignment) -> String {
+    let text = text.graphemes(true).take(width).collect::<String>();
+    let space = width - text.graphemes(true).count();
+
+    let left = match alignment {
+        HorizontalAlignment::Left => 0,
+        HorizontalAlignment::Center => space / 2,
+        HorizontalAlignment::Right => space
+    };
+
+    format!("{}{text}{}", " ".repeat(left), " ".repeat(space - left))
+}
+
+impl<Ctx> Widget<Ctx> for Table<Ctx> {
+    fn sizing(&self, _bounds: &Rect, _context: &mut Ctx) -> ItemSizing {
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Greedy(1))
+    }
+
+    fn render(&self, size: &Size, context: &mut Ctx) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+
+        let indent_width = self.highlight_symbol.graphemes(true).count();
+        let indent = " ".repeat(indent_width);
+        let widths = self.column_widths(size.width.saturating_sub(indent_width));
+
+        let mut rows = vec![];
+
+        if let Some(header) = &self.header {
+            let row = self.row_layout(header, &widths, &indent);
+
+            rows.push(match self.header_separator {
+                Some(c) => row.border(1, c, hash_set!(Edge::Bottom)),
+                None => row
+            });
+        }
+
+        let header_height = self.header.as_ref().map_or(0, |_| 1 + usize::from(self.header_separator.is_some()));
+        let height = size.height.saturating_sub(header_height);
+
+        let state = (self.state)(context);
+        state.scroll_to_selection(self.rows.len(), height);
+
+        for (index, row) in self.rows.iter().enumerate().skip(state.offset()).take(height) {
+            let prefix = if state.selected() == Some(index) { &self.highlight_symbol } else { &indent };
+
+            rows.push(self.row_layout(row, &widths, prefix));
+        }
+
+        let table = Layout::VerticalStack(HorizontalAlignment::Left, 0, rows).align_top().align_left();
+        canvas.render_layout(&table, &mut ());
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(row: &[&str]) -> Vec<String> {
+        row.iter().map(|cell| cell.to_string()).collect()
+    }
+
+    fn table(rows: &[&[&str]]) -> Table<TableState> {
+        Table::new(rows.iter().map(|row| cells(row)).collect(), |state: &mut TableState| state)
+    }
+
+    fn rendered(table: &Table<TableState>, state: &mut TableState, width: usize, height: usize) -> Vec<String> {
+        table.render(&Size::new(width, height), state).to_string().lines()
+            .map(|line| line.trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn min_columns_keep_their_minimum_after_greedy_ones() {
+        let table = table(&[&["a long first cell", "b"]])
+            .widths(vec![Constraint::FitContent, Constraint::Min(5)]);
+
+        assert_eq!(table.column_widths(12), vec![6, 5]);
+        assert_eq!(table.column_widths(30), vec![17, 12]);
+    }
+
+    #[test]
+    fn columns_share_what_the_constraints_leave() {
+        let table = table(&[&["abc", "de", "f"]])
+            .widths(vec![Constraint::Fixed(4), Constraint::Percentage(50), Constraint::Max(2)]);
+
+        // 2 gaps of one column
+        assert_eq!(table.column_widths(22), vec![4, 10, 1]);
+        assert_eq!(table.column_widths(6), vec![4, 0, 0]);
+    }
+
+    #[test]
+    fn separators_are_drawn_as_borders() {
+        let table = table(&[&["a", "bb"], &["ccc", "d"]])
+            .header(cells(&["x", "y"]))
+            .separator('|')
+            .header_separator('-')
+            .highlight_symbol("");
+
+        assert_eq!(rendered(&table, &mut TableState::new(), 10, 4), vec![
+            "x   | y",
+            "--------",
+            "a   | bb",
+            "ccc | d"
+        ]);
+    }
+
+    #[test]
+    fn there_is_no_header_separator_without_a_header() {
+        let table = table(&[&["a"], &["b"]]).header_separator('-').highlight_symbol("");
+
+        assert_eq!(rendered(&table, &mut TableState::new(), 3, 2), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn the_selected_row_scrolls_into_view_under_the_header() {
+        let table = table(&[&["1"], &["2"], &["3"]]).header(cells(&["#"]));
+        let mut state = TableState::new();
+        state.select(Some(2));
+
+        assert_eq!(rendered(&table, &mut state, 4, 2), vec!["  #", "> 3"]);
+        assert_eq!(state.offset(), 2);
+    }
+}
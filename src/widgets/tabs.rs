@@ -0,0 +1,292 @@
+use std::collections::HashSet;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{animation::{AnimationEvent, KeyCode, KeyModifiers}, canvas::TextCanvas, layout::{alignment::{Edge, HorizontalAlignment, VerticalAlignment}, geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}, Layout}};
+
+use super::Widget;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TabsState {
+    selected: usize,
+    // How many tabs it was last rendered with
+    count: usize
+}
+
+impl TabsState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Rendering keeps this in step with the tabs. Setting it lets keys work before the first render.
+    pub fn set_count(&mut self, count: usize) {
+        self.count = count;
+        self.selected = self.selected.min(count.saturating_sub(1));
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.count.saturating_sub(1));
+    }
+
+    pub fn next(&mut self) {
+        if self.count == 0 { return; }
+
+        self.selected = (self.selected + 1) % self.count;
+    }
+
+    pub fn previous(&mut self) {
+        if self.count == 0 { return; }
+
+        self.selected = (self.selected + self.count - 1) % self.count;
+    }
+
+    pub fn handle_event<Msg>(&mut self, event: &AnimationEvent<Msg>) -> bool {
+        match event {
+            AnimationEvent::KeyEvent(code, modifiers) => self.handle_key(*code, *modifiers),
+            _ => false
+        }
+    }
+
+    // Left and Right go around, 1 to 9 pick a tab. Returns true when the key was used.
+    pub fn handle_key(&mut self, code: KeyCode, _modifiers: KeyModifiers) -> bool {
+        match code {
+            KeyCode::Left => self.previous(),
+            KeyCode::Right => self.next(),
+            KeyCode::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if index >= self.count { return false; }
+
+                self.selected = index;
+            }
+            _ => return false
+        }
+
+        true
+    }
+}
+
+// One row of titles, laid out next to each other. When they don't fit, it shows the ones
+// around the selected tab with arrows on the sides that have more.
+struct TabBar<Ctx> {
+    titles: Vec<Layout<Ctx>>,
+    selected: usize,
+    divider: String,
+    state: fn(&mut Ctx) -> &mut TabsState
+}
+
+impl<Ctx: Clone> TabBar<Ctx> {
+    // How wide and tall each title is
+    fn sizes(&self, bounds: &Rect, context: &mut Ctx) -> Vec<(usize, usize)> {
+        self.titles.iter()
+            .map(|title| {
+                let sizing = title.resolve_size(bounds, context).sizing;
+                (sizing.horizontal.min_content_size(), sizing.vertical.min_content_size())
+            })
+            .collect()
+    }
+
+    fn width(&self, widths: &[usize]) -> usize {
+        let dividers = widths.len().saturating_sub(1) * self.divider.graphemes(true).count();
+
+        widths.iter().sum::<usize>() + dividers
+    }
+
+    // The range of titles in view, growing from the selected one to the right, then to the left
+    fn visible(&self, widths: &[usize], width: usize) -> (usize, usize) {
+        let selected = self.selected.min(widths.len() - 1);
+        let (mut start, mut end) = (selected, selected + 1);
+
+        while end < widths.len() && self.width(&widths[start..end + 1]) <= width { end += 1; }
+        while start > 0 && self.width(&widths[start - 1..end]) <= width { start -= 1; }
+
+        (start, end)
+    }
+}
+
+impl<Ctx: Clone> Widget<Ctx> for TabBar<Ctx> {
+    fn sizing(&self, bounds: &Rect, context: &mut Ctx) -> ItemSizing {
+        let height = self.sizes(bounds, context).into_iter().map(|(_, height)| height).max().unwrap_or(0);
+
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Static(height.max(1)))
+    }
+
+    fn render(&self, size: &Size, context: &mut Ctx) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+
+        (self.state)(context).set_count(self.titles.len());
+        if self.titles.is_empty() { return canvas; }
+
+        let widths = self.sizes(&Rect::sized(size.width, size.height), context).into_iter()
+            .map(|(width, _)| width)
+            .collect::<Vec<_>>();
+
+        // Room for "< " and " >"
+        let (start, end, x) = if self.width(&widths) > size.width {
+            let (start, end) = self.visible(&widths, size.width.saturating_sub(4));
+            (start, end, 2)
+        } else {
+            (0, self.titles.len(), 0)
+        };
+
+        let mut nodes = vec![];
+        for (i, title) in self.titles[start..end].iter().enumerate() {
+            if i > 0 { nodes.push(Layout::text(&self.divider)); }
+            nodes.push(title.clone());
+        }
+
+        let bar = Layout::HorizontalStack(VerticalAlignment::Top, 0, nodes).padding_left(x).align_top().align_left();
+        canvas.render_layout(&bar, context);
+
+        if start > 0 { canvas.write("<", 0, 0); }
+        if end < self.titles.len() { canvas.write(">", size.width.saturating_sub(1), 0); }
+
+        canvas
+    }
+}
+
+// A tab bar over the content of the selected tab
+pub struct Tabs<Ctx: Clone> {
+    titles: Vec<Layout<Ctx>>,
+    contents: Vec<Layout<Ctx>>,
+    selected: usize,
+    state: fn(&mut Ctx) -> &mut TabsState,
+    divider: String,
+    highlight_style: fn(Layout<Ctx>) -> Layout<Ctx>,
+    border: (char, HashSet<Edge>)
+}
+
+impl<Ctx: Clone + 'static> Tabs<Ctx> {
+    // The tab shown is the one selected in `selected`. `state` gets the tab count when it's rendered.
+    pub fn new(selected: &TabsState, state: fn(&mut Ctx) -> &mut TabsState) -> Self {
+        Tabs {
+            titles: vec![],
+            contents: vec![],
+            selected: selected.selected(),
+            state,
+            divider: "|".to_string(),
+            highlight_style: |title| Layout::HorizontalStack(VerticalAlignment::Top, 0, vec![Layout::text("["), title, Layout::text("]")]),
+            border: ('─', hash_set!(Edge::Top))
+        }
+    }
+
+    pub fn tab(self, title: &str, content: Layout<Ctx>) -> Self {
+        self.tab_with(Layout::text(title), content)
+    }
+
+    // A tab with any layout for a title
+    pub fn tab_with(mut self, title: Layout<Ctx>, content: Layout<Ctx>) -> Self {
+        self.titles.push(title);
+        self.contents.push(content);
+        self
+    }
+
+    // Drawn between titles
+    pub fn divider(mut self, divider: &str) -> Self {
+        self.divider = divider.to_string();
+        self
+    }
+
+    // Wraps the title of the selected tab. Other titles are padded with a space on each side.
+    pub fn highlight_style(mut self, style: fn(Layout<Ctx>) -> Layout<Ctx>) -> Self {
+        self.highlight_style = style;
+        self
+    }
+
+    // Framing the content, under the tab bar
+    pub fn border(mut self, c: char, edges: HashSet<Edge>) -> Self {
+        self.border = (c, edges);
+        self
+    }
+
+    pub fn layout(mut self) -> Layout<Ctx> {
+        let selected = self.selected.min(self.contents.len().saturating_sub(1));
+
+        let titles = self.titles.into_iter().enumerate()
+            .map(|(i, title)| if i == selected { (self.highlight_style)(title) } else { title.padding_horizontal(1) })
+            .collect();
+
+        let bar = TabBar { titles, selected, divider: self.divider, state: self.state };
+
+        let content = match self.contents.is_empty() {
+            true => Layout::text(""),
+            false => self.contents.swap_remove(selected)
+        };
+
+        let (c, edges) = self.border;
+
+        Layout::VerticalStack(HorizontalAlignment::Left, 0, vec![
+            Layout::widget(bar),
+            content.border(1, c, edges)
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(tabs: Tabs<TabsState>, state: &mut TabsState, width: usize, height: usize) -> Vec<String> {
+        let mut canvas = TextCanvas::create_in_bounds(&Size::new(width, height));
+        canvas.render_layout(&tabs.layout(), state);
+
+        canvas.to_string().lines().map(|line| line.trim_end().to_string()).collect()
+    }
+
+    fn tabs(state: &TabsState) -> Tabs<TabsState> {
+        Tabs::new(state, |s: &mut TabsState| s)
+            .tab("one", Layout::text("first"))
+            .tab("two", Layout::text("second"))
+            .tab("three", Layout::text("third"))
+    }
+
+    #[test]
+    fn rendering_counts_the_tabs() {
+        let mut state = TabsState::new();
+        assert!(!state.handle_key(KeyCode::Char('2'), KeyModifiers::NONE));
+
+        rendered(tabs(&state), &mut state, 20, 3);
+        state.previous();
+        assert_eq!(state.selected(), 2);
+
+        assert_eq!(rendered(tabs(&state), &mut state, 20, 3), vec![
+            " one | two |[three]",
+            "─────",
+            "third"
+        ]);
+
+        state.set_count(2);
+        assert_eq!(state.selected(), 1);
+    }
+
+    #[test]
+    fn titles_that_do_not_fit_scroll_with_arrows() {
+        let mut state = TabsState::new();
+        state.set_count(3);
+        state.select(1);
+
+        assert_eq!(rendered(tabs(&state), &mut state, 13, 3)[0], "< [two]     >");
+        assert_eq!(rendered(tabs(&state), &mut state, 16, 3)[0], "   one |[two]  >");
+    }
+
+    #[test]
+    fn titles_can_be_any_layout() {
+        let mut state = TabsState::new();
+
+        let tabs = Tabs::new(&state, |s: &mut TabsState| s)
+            .tab_with(Layout::text("a").border(1, '*', hash_set!(Edge::Bottom)), Layout::text("first"))
+            .tab("b", Layout::text("second"))
+            .highlight_style(|title| title.padding_left(1))
+            .divider("");
+
+        assert_eq!(rendered(tabs, &mut state, 10, 4), vec![
+            " a b",
+            " *",
+            "─────",
+            "first"
+        ]);
+    }
+}
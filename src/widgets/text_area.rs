@@ -0,0 +1,738 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{animation::{AnimationEvent, KeyCode, KeyModifiers}, canvas::TextCanvas, layout::{geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}}};
+
+use super::{inverted, text_input::{next_word_end, previous_word_start}, Widget};
+
+// A line and a grapheme within it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Position { line, column }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Snapshot {
+    lines: Vec<String>,
+    cursor: Position
+}
+
+// Consecutive edits of the same kind are undone together
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EditKind {
+    Typing,
+    Deleting,
+    Other
+}
+
+// A row of the soft wrapped text: the line it belongs to and the graphemes it shows
+#[derive(Clone, Debug, PartialEq)]
+struct VisualRow {
+    line: usize,
+    start: usize,
+    end: usize
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextAreaState {
+    lines: Vec<String>,
+    cursor: Position,
+    anchor: Option<Position>,
+    // The first visual row in view
+    scroll: usize,
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+    last_edit: Option<EditKind>,
+    // The size it was last rendered at, for moving by rows and pages
+    width: usize,
+    height: usize
+}
+
+impl Default for TextAreaState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextAreaState {
+    pub fn new() -> Self {
+        TextAreaState {
+            lines: vec![String::new()],
+            cursor: Position::default(),
+            anchor: None,
+            scroll: 0,
+            undo_stack: vec![],
+            redo_stack: vec![],
+            last_edit: None,
+            width: 0,
+            height: 0
+        }
+    }
+
+    pub fn with_text(text: &str) -> Self {
+        let mut state = Self::new();
+        state.set_text(text);
+
+        state
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    // Starts over, history included, with the cursor at the start
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = split_lines(text);
+        self.cursor = Position::default();
+        self.anchor = None;
+        self.scroll = 0;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.last_edit = None;
+    }
+
+    pub fn cursor(&self) -> Position {
+        self.cursor
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    pub fn selection(&self) -> Option<(Position, Position)> {
+        let anchor = self.anchor?;
+        if anchor == self.cursor { return None; }
+
+        Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+
+        Some(self.text_between(start, end))
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(Position::default());
+        self.cursor = self.end_of_text();
+    }
+
+    // The selected text, for the app to put wherever its clipboard is
+    pub fn copy(&self) -> Option<String> {
+        self.selected_text()
+    }
+
+    pub fn cut(&mut self) -> Option<String> {
+        let text = self.selected_text()?;
+
+        self.record_edit(EditKind::Other);
+        self.delete_selection();
+
+        Some(text)
+    }
+
+    // Replaces the selection, if any
+    pub fn insert(&mut self, text: &str) {
+        let kind = if text.graphemes(true).count() == 1 && text != "\n" { EditKind::Typing } else { EditKind::Other };
+        self.record_edit(kind);
+
+        self.delete_selection();
+        self.insert_at_cursor(text);
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo_stack.pop() else { return false };
+
+        self.redo_stack.push(self.snapshot());
+        self.restore(snapshot);
+
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo_stack.pop() else { return false };
+
+        self.undo_stack.push(self.snapshot());
+        self.restore(snapshot);
+
+        true
+    }
+
+    // Key presses and pastes. Returns true when the event changed or moved something.
+    pub fn handle_event<Msg>(&mut self, event: &AnimationEvent<Msg>) -> bool {
+        match event {
+            AnimationEvent::KeyEvent(code, modifiers) => self.handle_key(*code, *modifiers),
+            AnimationEvent::Paste(text) => {
+                self.insert(text);
+                true
+            }
+            _ => false
+        }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let control = modifiers.contains(KeyModifiers::CONTROL);
+        let alt = modifiers.contains(KeyModifiers::ALT);
+        let extend = modifiers.contains(KeyModifiers::SHIFT);
+
+        let line_start = Position::new(self.cursor.line, 0);
+        let line_end = Position::new(self.cursor.line, self.line_len(self.cursor.line));
+
+        match code {
+            // Ctrl-Z suspends the program, so undo follows nano
+            KeyCode::Char('u') if alt => { self.undo(); }
+            KeyCode::Char('e') if alt => { self.redo(); }
+            KeyCode::Char('w') if control => self.delete_to(self.previous_word()),
+            KeyCode::Backspace if control || alt => self.delete_to(self.previous_word()),
+            KeyCode::Char('u') if control => self.delete_to(line_start),
+            KeyCode::Char('k') if control => self.delete_to(line_end),
+            KeyCode::Char('a') if control => self.move_to(line_start, extend),
+            KeyCode::Char('e') if control => self.move_to(line_end, extend),
+            KeyCode::Char(c) if !control && !alt => self.insert(&c.to_string()),
+            KeyCode::Enter => self.insert("\n"),
+            KeyCode::Backspace => {
+                if self.selection().is_none() {
+                    self.delete_to(self.position_before(self.cursor));
+                } else {
+                    self.record_edit(EditKind::Other);
+                    self.delete_selection();
+                }
+            }
+            KeyCode::Delete => {
+                if self.selection().is_none() {
+                    self.delete_to(self.position_after(self.cursor));
+                } else {
+                    self.record_edit(EditKind::Other);
+                    self.delete_selection();
+                }
+            }
+            KeyCode::Left if control || alt => self.move_to(self.previous_word(), extend),
+            KeyCode::Right if control || alt => self.move_to(self.next_word(), extend),
+            KeyCode::Left => self.move_to(self.position_before(self.cursor), extend),
+            KeyCode::Right => self.move_to(self.position_after(self.cursor), extend),
+            KeyCode::Up => self.move_to(self.row_above(self.cursor), extend),
+            KeyCode::Down => self.move_to(self.row_below(self.cursor), extend),
+            KeyCode::PageUp => {
+                let position = (0..self.height.max(1)).fold(self.cursor, |p, _| self.row_above(p));
+                self.move_to(position, extend);
+            }
+            KeyCode::PageDown => {
+                let position = (0..self.height.max(1)).fold(self.cursor, |p, _| self.row_below(p));
+                self.move_to(position, extend);
+            }
+            KeyCode::Home if control => self.move_to(Position::default(), extend),
+            KeyCode::End if control => self.move_to(self.end_of_text(), extend),
+            KeyCode::Home => self.move_to(line_start, extend),
+            KeyCode::End => self.move_to(line_end, extend),
+            _ => return false
+        }
+
+        true
+    }
+}
+
+// Editing
+impl TextAreaState {
+    fn snapshot(&self) -> Snapshot {
+        Snapshot { lines: self.lines.clone(), cursor: self.cursor }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.lines = snapshot.lines;
+        self.cursor = snapshot.cursor;
+        self.anchor = None;
+        self.last_edit = None;
+    }
+
+    fn record_edit(&mut self, kind: EditKind) {
+        if kind == EditKind::Other || self.last_edit != Some(kind) {
+            self.undo_stack.push(self.snapshot());
+        }
+
+        self.redo_stack.clear();
+        self.last_edit = Some(kind);
+    }
+
+    fn insert_at_cursor(&mut self, text: &str) {
+        let inserted = split_lines(text);
+        let Position { line, column } = self.cursor;
+
+        let index = byte_index(&self.lines[line], column);
+        let after = self.lines[line].split_off(index);
+
+        let last_line = line + inserted.len() - 1;
+        let last_column = match inserted.len() {
+            1 => column + inserted[0].graphemes(true).count(),
+            _ => inserted[inserted.len() - 1].graphemes(true).count()
+        };
+
+        self.lines[line].push_str(&inserted[0]);
+        self.lines.splice(line + 1..line + 1, inserted[1..].iter().cloned());
+        self.lines[last_line].push_str(&after);
+
+        self.cursor = Position::new(last_line, last_column);
+    }
+
+    fn delete_range(&mut self, start: Position, end: Position) {
+        let tail = self.lines[end.line][byte_index(&self.lines[end.line], end.column)..].to_string();
+
+        let line = &mut self.lines[start.line];
+        line.truncate(byte_index(line, start.column));
+        line.push_str(&tail);
+
+        self.lines.drain(start.line + 1..=end.line);
+        self.cursor = start;
+    }
+
+    // Deletes between the cursor and `position`, in whichever direction
+    fn delete_to(&mut self, position: Position) {
+        if position == self.cursor { return; }
+
+        self.record_edit(EditKind::Deleting);
+        self.delete_range(self.cursor.min(position), self.cursor.max(position));
+        self.anchor = None;
+    }
+
+    fn delete_selection(&mut self) {
+        if let Some((start, end)) = self.selection() {
+            self.delete_range(start, end);
+        }
+
+        self.anchor = None;
+    }
+
+    fn text_between(&self, start: Position, end: Position) -> String {
+        let graphemes = |line: usize| self.lines[line].graphemes(true).collect::<Vec<_>>();
+
+        if start.line == end.line {
+            return graphemes(start.line)[start.column..end.column].concat();
+        }
+
+        let mut parts = vec![graphemes(start.line)[start.column..].concat()];
+        parts.extend(self.lines[start.line + 1..end.line].iter().cloned());
+        parts.push(graphemes(end.line)[..end.column].concat());
+
+        parts.join("\n")
+    }
+}
+
+// Movement
+impl TextAreaState {
+    fn line_len(&self, line: usize) -> usize {
+        self.lines[line].graphemes(true).count()
+    }
+
+    fn end_of_text(&self) -> Position {
+        let line = self.lines.len() - 1;
+
+        Position::new(line, self.line_len(line))
+    }
+
+    fn move_to(&mut self, position: Position, extend: bool) {
+        if extend {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+
+        self.cursor = position;
+        self.last_edit = None;
+    }
+
+    fn position_before(&self, position: Position) -> Position {
+        match (position.column, position.line) {
+            (0, 0) => position,
+            (0, line) => Position::new(line - 1, self.line_len(line - 1)),
+            (column, line) => Position::new(line, column - 1)
+        }
+    }
+
+    fn position_after(&self, position: Position) -> Position {
+        if position.column < self.line_len(position.line) {
+            Position::new(position.line, position.column + 1)
+        } else if position.line + 1 < self.lines.len() {
+            Position::new(position.line + 1, 0)
+        } else {
+            position
+        }
+    }
+
+    fn previous_word(&self) -> Position {
+        if self.cursor.column == 0 { return self.position_before(self.cursor); }
+
+        let graphemes = self.lines[self.cursor.line].graphemes(true).collect::<Vec<_>>();
+
+        Position::new(self.cursor.line, previous_word_start(&graphemes, self.cursor.column))
+    }
+
+    fn next_word(&self) -> Position {
+        if self.cursor.column == self.line_len(self.cursor.line) { return self.position_after(self.cursor); }
+
+        let graphemes = self.lines[self.cursor.line].graphemes(true).collect::<Vec<_>>();
+
+        Position::new(self.cursor.line, next_word_end(&graphemes, self.cursor.column))
+    }
+
+    // Wrapped rows are as wide as the area was last rendered, or whole lines before that
+    fn wrap_width(&self) -> usize {
+        if self.width == 0 { usize::MAX } else { self.width }
+    }
+
+    fn row_above(&self, position: Position) -> Position {
+        let width = self.wrap_width();
+        let (row, column) = (position.column / width, position.column % width);
+
+        if row > 0 {
+            return Position::new(position.line, (row - 1) * width + column);
+        }
+
+        if position.line == 0 { return Position::default(); }
+
+        let line = position.line - 1;
+        let len = self.line_len(line);
+        let last_row = len.saturating_sub(1) / width;
+
+        Position::new(line, (last_row * width + column).min(len))
+    }
+
+    fn row_below(&self, position: Position) -> Position {
+        let width = self.wrap_width();
+        let (row, column) = (position.column / width, position.column % width);
+        let len = self.line_len(position.line);
+
+        if (row + 1).saturating_mul(width) < len {
+            return Position::new(position.line, ((row + 1) * width + column).min(len));
+        }
+
+        if position.line + 1 == self.lines.len() { return self.end_of_text(); }
+
+        let line = position.line + 1;
+
+        Position::new(line, column.min(self.line_len(line)))
+    }
+}
+
+// Rendering
+impl TextAreaState {
+    // Text is wrapped at exactly `width` graphemes, like text layouts are. A cursor right after
+    // a line that fills its last row gets a row of its own.
+    fn visual_rows(&self, width: usize) -> Vec<VisualRow> {
+        let mut rows = vec![];
+
+        for (line, text) in self.lines.iter().enumerate() {
+            let len = text.graphemes(true).count();
+
+            let mut count = len.div_ceil(width).max(1);
+            if line == self.cursor.line && self.cursor.column == len && len > 0 && len.is_multiple_of(width) {
+                count += 1;
+            }
+
+            for row in 0..count {
+                rows.push(VisualRow { line, start: row * width, end: ((row + 1) * width).min(len) });
+            }
+        }
+
+        rows
+    }
+
+    fn cursor_row(&self, rows: &[VisualRow], width: usize) -> usize {
+        let row_in_line = self.cursor.column / width;
+
+        rows.iter()
+            .position(|r| r.line == self.cursor.line)
+            .map_or(0, |first| first + row_in_line)
+            .min(rows.len() - 1)
+    }
+
+    fn scroll_to_cursor(&mut self, rows: &[VisualRow], width: usize, height: usize) {
+        let cursor_row = self.cursor_row(rows, width);
+
+        if cursor_row < self.scroll {
+            self.scroll = cursor_row;
+        } else if cursor_row >= self.scroll + height {
+            self.scroll = cursor_row + 1 - height;
+        }
+
+        self.scroll = self.scroll.min(rows.len().saturating_sub(height));
+    }
+}
+
+fn split_lines(text: &str) -> Vec<String> {
+    text.replace("\r\n", "\n").split(['\n', '\r']).map(str::to_string).collect()
+}
+
+fn byte_index(line: &str, grapheme: usize) -> usize {
+    line.grapheme_indices(true)
+        .nth(grapheme)
+        .map_or(line.len(), |(i, _)| i)
+}
+
+// A scrolling, soft wrapped editor over a `TextAreaState` in the context
+pub struct TextArea<Ctx> {
+    state: fn(&mut Ctx) -> &mut TextAreaState,
+    placeholder: String,
+    show_cursor: bool,
+    selection_style: fn(&str) -> String
+}
+
+impl<Ctx> TextArea<Ctx> {
+    pub fn new(state: fn(&mut Ctx) -> &mut TextAreaState) -> Self {
+        TextArea { state, placeholder: String::new(), show_cursor: true, selection_style: inverted }
+    }
+
+    // Shown while there's no text
+    pub fn placeholder(mut self, text: &str) -> Self {
+        self.placeholder = text.to_string();
+        self
+    }
+
+    // Usually tied to whether the editor has focus
+    pub fn show_cursor(mut self, show: bool) -> Self {
+        self.show_cursor = show;
+        self
+    }
+
+    // Rewrites each selected grapheme. Defaults to inverting its colours.
+    pub fn selection_style(mut self, style: fn(&str) -> String) -> Self {
+        self.selection_style = style;
+        self
+    }
+}
+
+impl<Ctx> Widget<Ctx> for TextArea<Ctx> {
+    fn sizing(&self, _bounds: &Rect, _context: &mut Ctx) -> ItemSizing {
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Greedy(1))
+    }
+
+    fn render(&self, size: &Size, context: &mut Ctx) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+        if size.width == 0 || size.height == 0 { return canvas; }
+
+        let state = (self.state)(context);
+        state.width = size.width;
+        state.height = size.height;
+
+        let rows = state.visual_rows(size.width);
+        state.scroll_to_cursor(&rows, size.width, size.height);
+
+        let is_empty = state.lines.len() == 1 && state.lines[0].is_empty();
+
+        if is_empty {
+            for (x, grapheme) in self.placeholder.graphemes(true).take(size.width).enumerate() {
+                canvas.write(grapheme, x, 0);
+            }
+        } else {
+            let selection = state.selection();
+
+            for (y, row) in rows.iter().skip(state.scroll).take(size.height).enumerate() {
+                let graphemes = state.lines[row.line].graphemes(true).skip(row.start).take(row.end - row.start);
+
+                for (x, grapheme) in graphemes.enumerate() {
+                    let position = Position::new(row.line, row.start + x);
+
+                    match selection {
+                        Some((start, end)) if start <= position && position < end => {
+                            canvas.write(&(self.selection_style)(grapheme), x, y);
+                        }
+                        _ => canvas.write(grapheme, x, y)
+                    }
+                }
+            }
+        }
+
+        if self.show_cursor {
+            let y = state.cursor_row(&rows, size.width) - state.scroll;
+            canvas.set_cursor(state.cursor.column % size.width, y);
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(state: &mut TextAreaState, code: KeyCode, modifiers: KeyModifiers) {
+        state.handle_key(code, modifiers);
+    }
+
+    fn type_text(state: &mut TextAreaState, text: &str) {
+        for c in text.chars() {
+            let code = if c == '\n' { KeyCode::Enter } else { KeyCode::Char(c) };
+            key(state, code, KeyModifiers::NONE);
+        }
+    }
+
+    fn rendered(state: &mut TextAreaState, width: usize, height: usize) -> TextCanvas {
+        TextArea::new(|state: &mut TextAreaState| state).render(&Size::new(width, height), state)
+    }
+
+    #[test]
+    fn typing_is_undone_a_run_at_a_time() {
+        let mut state = TextAreaState::new();
+
+        type_text(&mut state, "one");
+        key(&mut state, KeyCode::Backspace, KeyModifiers::NONE);
+        key(&mut state, KeyCode::Backspace, KeyModifiers::NONE);
+        type_text(&mut state, "ld\nbar");
+        assert_eq!(state.text(), "old\nbar");
+
+        assert!(state.undo());
+        assert_eq!(state.text(), "old\n");
+        assert!(state.undo());
+        assert_eq!(state.text(), "old");
+        assert!(state.undo());
+        assert_eq!(state.text(), "o");
+        assert!(state.undo());
+        assert_eq!(state.text(), "one");
+        assert!(state.undo());
+        assert_eq!(state.text(), "");
+        assert!(!state.undo());
+    }
+
+    #[test]
+    fn redo_puts_back_what_undo_took_until_something_else_is_edited() {
+        let mut state = TextAreaState::new();
+        type_text(&mut state, "abc");
+
+        key(&mut state, KeyCode::Char('u'), KeyModifiers::ALT);
+        assert_eq!(state.text(), "");
+
+        key(&mut state, KeyCode::Char('e'), KeyModifiers::ALT);
+        assert_eq!(state.text(), "abc");
+        assert_eq!(state.cursor(), Position::new(0, 3));
+
+        state.undo();
+        type_text(&mut state, "x");
+        assert!(!state.redo());
+        assert_eq!(state.text(), "x");
+    }
+
+    #[test]
+    fn moving_the_cursor_starts_a_new_undo_step() {
+        let mut state = TextAreaState::new();
+        type_text(&mut state, "ab");
+        key(&mut state, KeyCode::Left, KeyModifiers::NONE);
+        type_text(&mut state, "c");
+
+        state.undo();
+        assert_eq!(state.text(), "ab");
+    }
+
+    #[test]
+    fn set_text_forgets_the_history() {
+        let mut state = TextAreaState::new();
+        type_text(&mut state, "draft");
+
+        state.set_text("fresh");
+        assert!(!state.undo());
+        assert_eq!(state.cursor(), Position::default());
+    }
+
+    #[test]
+    fn word_motion_crosses_line_breaks() {
+        let mut state = TextAreaState::with_text("one two\nthree");
+        key(&mut state, KeyCode::End, KeyModifiers::CONTROL);
+
+        key(&mut state, KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(state.cursor(), Position::new(1, 0));
+
+        key(&mut state, KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(state.cursor(), Position::new(0, 7));
+
+        key(&mut state, KeyCode::Left, KeyModifiers::ALT);
+        assert_eq!(state.cursor(), Position::new(0, 4));
+
+        key(&mut state, KeyCode::Right, KeyModifiers::CONTROL);
+        key(&mut state, KeyCode::Right, KeyModifiers::CONTROL);
+        assert_eq!(state.cursor(), Position::new(1, 0));
+    }
+
+    #[test]
+    fn deleting_a_word_at_the_start_of_a_line_joins_it_to_the_one_before() {
+        let mut state = TextAreaState::with_text("one two\nthree");
+        key(&mut state, KeyCode::Down, KeyModifiers::NONE);
+
+        key(&mut state, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(state.text(), "one twothree");
+
+        key(&mut state, KeyCode::Backspace, KeyModifiers::ALT);
+        assert_eq!(state.text(), "one three");
+
+        // Deleting in a row undoes as one step
+        state.undo();
+        assert_eq!(state.text(), "one two\nthree");
+    }
+
+    #[test]
+    fn cutting_the_selection_can_be_undone() {
+        let mut state = TextAreaState::with_text("ab\ncd");
+        key(&mut state, KeyCode::Right, KeyModifiers::NONE);
+        key(&mut state, KeyCode::Down, KeyModifiers::SHIFT);
+
+        assert_eq!(state.cut(), Some("b\nc".to_string()));
+        assert_eq!(state.text(), "ad");
+
+        state.undo();
+        assert_eq!(state.text(), "ab\ncd");
+    }
+
+    #[test]
+    fn up_and_down_move_by_wrapped_rows() {
+        let mut state = TextAreaState::with_text("abcdefgh\nxy");
+        rendered(&mut state, 4, 3);
+
+        key(&mut state, KeyCode::Right, KeyModifiers::NONE);
+        key(&mut state, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(state.cursor(), Position::new(0, 5));
+
+        key(&mut state, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(state.cursor(), Position::new(1, 1));
+
+        key(&mut state, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(state.cursor(), Position::new(0, 5));
+    }
+
+    #[test]
+    fn the_view_scrolls_to_the_cursor() {
+        let mut state = TextAreaState::with_text("a\nb\nc\nd");
+        key(&mut state, KeyCode::End, KeyModifiers::CONTROL);
+
+        let canvas = rendered(&mut state, 3, 2);
+        assert_eq!(state.scroll(), 2);
+        assert_eq!(canvas.to_string().lines().map(str::trim_end).collect::<Vec<_>>(), vec!["c", "d"]);
+        assert_eq!(canvas.cursor(), Some((1, 1)));
+    }
+
+    #[test]
+    fn a_selection_is_drawn_across_wrapped_rows() {
+        let mut state = TextAreaState::new();
+        type_text(&mut state, "abcdef\ngh");
+        key(&mut state, KeyCode::Up, KeyModifiers::NONE);
+        key(&mut state, KeyCode::Home, KeyModifiers::NONE);
+        key(&mut state, KeyCode::Right, KeyModifiers::NONE);
+        key(&mut state, KeyCode::Right, KeyModifiers::NONE);
+        for _ in 0..3 {
+            key(&mut state, KeyCode::Right, KeyModifiers::SHIFT);
+        }
+        assert_eq!(state.selection(), Some((Position::new(0, 2), Position::new(0, 5))));
+
+        let canvas = TextArea::new(|state: &mut TextAreaState| state)
+            .selection_style(|grapheme| grapheme.to_uppercase())
+            .render(&Size::new(4, 3), &mut state);
+
+        assert_eq!(canvas.to_string(), "abCD\nEf  \ngh  ");
+        assert_eq!(rendered(&mut state, 4, 3).get_at(0, 1), Some(inverted("e").as_str()));
+    }
+}
@@ -0,0 +1,445 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{animation::{AnimationEvent, KeyCode, KeyModifiers}, canvas::TextCanvas, layout::{geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}}};
+
+use super::{inverted, Widget};
+
+// Positions are in graphemes, each drawn in one cell
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextInputState {
+    value: String,
+    cursor: usize,
+    // Where the selection started, the cursor being its other end
+    anchor: Option<usize>,
+    // The first grapheme in view
+    offset: usize
+}
+
+impl TextInputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_value(value: &str) -> Self {
+        let mut state = Self::new();
+        state.set_value(value);
+
+        state
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    // Puts the cursor at the end, the view scrolls back to it on the next render
+    pub fn set_value(&mut self, value: &str) {
+        self.value = single_line(value);
+        self.cursor = self.len();
+        self.anchor = None;
+        self.offset = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_value("");
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        if anchor == self.cursor { return None; }
+
+        Some(anchor.min(self.cursor)..anchor.max(self.cursor))
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        let selection = self.selection()?;
+
+        Some(self.graphemes()[selection].concat())
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.len();
+    }
+
+    // Replaces the selection, if any
+    pub fn insert(&mut self, text: &str) {
+        self.delete_selection();
+
+        let text = single_line(text);
+        let index = self.byte_index(self.cursor);
+        self.value.insert_str(index, &text);
+        self.cursor += text.graphemes(true).count();
+    }
+
+    // Key presses and pastes. Returns true when the event changed or moved something.
+    pub fn handle_event<Msg>(&mut self, event: &AnimationEvent<Msg>) -> bool {
+        match event {
+            AnimationEvent::KeyEvent(code, modifiers) => self.handle_key(*code, *modifiers),
+            AnimationEvent::Paste(text) => {
+                self.insert(text);
+                true
+            }
+            _ => false
+        }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let control = modifiers.contains(KeyModifiers::CONTROL);
+        let alt = modifiers.contains(KeyModifiers::ALT);
+        // Shift with a movement key extends the selection
+        let extend = modifiers.contains(KeyModifiers::SHIFT);
+
+        match code {
+            KeyCode::Char('w') if control => self.delete_to(self.previous_word()),
+            KeyCode::Backspace if control || alt => self.delete_to(self.previous_word()),
+            KeyCode::Char('u') if control => self.delete_to(0),
+            KeyCode::Char('k') if control => self.delete_to(self.len()),
+            KeyCode::Char('a') if control => self.move_to(0, extend),
+            KeyCode::Char('e') if control => self.move_to(self.len(), extend),
+            KeyCode::Char(c) if !control && !alt => self.insert(&c.to_string()),
+            KeyCode::Backspace | KeyCode::Delete if self.selection().is_some() => self.delete_selection(),
+            KeyCode::Backspace => self.delete_to(self.cursor.saturating_sub(1)),
+            KeyCode::Delete => self.delete_to((self.cursor + 1).min(self.len())),
+            KeyCode::Left if control || alt => self.move_to(self.previous_word(), extend),
+            KeyCode::Right if control || alt => self.move_to(self.next_word(), extend),
+            KeyCode::Left => self.move_to(self.cursor.saturating_sub(1), extend),
+            KeyCode::Right => self.move_to((self.cursor + 1).min(self.len()), extend),
+            KeyCode::Home => self.move_to(0, extend),
+            KeyCode::End => self.move_to(self.len(), extend),
+            _ => return false
+        }
+
+        true
+    }
+
+    fn graphemes(&self) -> Vec<&str> {
+        self.value.graphemes(true).collect()
+    }
+
+    fn len(&self) -> usize {
+        self.value.graphemes(true).count()
+    }
+
+    fn byte_index(&self, grapheme: usize) -> usize {
+        self.value.grapheme_indices(true)
+            .nth(grapheme)
+            .map_or(self.value.len(), |(i, _)| i)
+    }
+
+    fn move_to(&mut self, position: usize, extend: bool) {
+        if extend {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+
+        self.cursor = position;
+    }
+
+    // Deletes between the cursor and `position`, in whichever direction
+    fn delete_to(&mut self, position: usize) {
+        let range = self.cursor.min(position)..self.cursor.max(position);
+        let bytes = self.byte_index(range.start)..self.byte_index(range.end);
+
+        self.value.replace_range(bytes, "");
+        self.cursor = range.start;
+        self.anchor = None;
+    }
+
+    fn delete_selection(&mut self) {
+        if let Some(selection) = self.selection() {
+            self.cursor = selection.end;
+            self.delete_to(selection.start);
+        }
+
+        self.anchor = None;
+    }
+
+    fn previous_word(&self) -> usize {
+        previous_word_start(&self.graphemes(), self.cursor)
+    }
+
+    fn next_word(&self) -> usize {
+        next_word_end(&self.graphemes(), self.cursor)
+    }
+
+    // Moves the view just enough for the cursor to be in it, including past the last grapheme
+    fn scroll_to_cursor(&mut self, width: usize) {
+        if width == 0 { return; }
+
+        let len = self.len();
+        self.cursor = self.cursor.min(len);
+
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + width {
+            self.offset = self.cursor + 1 - width;
+        }
+
+        // Don't leave room at the end while there's text scrolled out on the left
+        self.offset = self.offset.min((len + 1).saturating_sub(width));
+    }
+}
+
+fn single_line(text: &str) -> String {
+    text.replace(['\r', '\n'], "")
+}
+
+fn is_word(grapheme: &str) -> bool {
+    !grapheme.chars().all(char::is_whitespace)
+}
+
+// Where the word before `position` starts, skipping the whitespace in between
+pub(crate) fn previous_word_start(graphemes: &[&str], position: usize) -> usize {
+    let mut i = position.min(graphemes.len());
+
+    while i > 0 && !is_word(graphemes[i - 1]) { i -= 1; }
+    while i > 0 && is_word(graphemes[i - 1]) { i -= 1; }
+
+    i
+}
+
+// Where the word after `position` ends, skipping the whitespace in between
+pub(crate) fn next_word_end(graphemes: &[&str], position: usize) -> usize {
+    let mut i = position.min(graphemes.len());
+
+    while i < graphemes.len() && !is_word(graphemes[i]) { i += 1; }
+    while i < graphemes.len() && is_word(graphemes[i]) { i += 1; }
+
+    i
+}
+
+// A one line field over a `TextInputState` in the context
+pub struct TextInput<Ctx> {
+    state: fn(&mut Ctx) -> &mut TextInputState,
+    placeholder: String,
+    mask: Option<char>,
+    show_cursor: bool,
+    selection_style: fn(&str) -> String
+}
+
+impl<Ctx> TextInput<Ctx> {
+    pub fn new(state: fn(&mut Ctx) -> &mut TextInputState) -> Self {
+        TextInput { state, placeholder: String::new(), mask: None, show_cursor: true, selection_style: inverted }
+    }
+
+    // Shown while the value is empty
+    pub fn placeholder(mut self, text: &str) -> Self {
+        self.placeholder = text.to_string();
+        self
+    }
+
+    // Draws every grapheme of the value as `mask`
+    pub fn password(mut self, mask: char) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    // Usually tied to whether the field has focus
+    pub fn show_cursor(mut self, show: bool) -> Self {
+        self.show_cursor = show;
+        self
+    }
+
+    // Rewrites each selected grapheme. Defaults to inverting its colours.
+    pub fn selection_style(mut self, style: fn(&str) -> String) -> Self {
+        self.selection_style = style;
+        self
+    }
+}
+
+impl<Ctx> Widget<Ctx> for TextInput<Ctx> {
+    fn sizing(&self, _bounds: &Rect, _context: &mut Ctx) -> ItemSizing {
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Static(1))
+    }
+
+    fn render(&self, size: &Size, context: &mut Ctx) -> TextCanvas {
+        let state = (self.state)(context);
+        state.scroll_to_cursor(size.width);
+
+        let mut canvas = TextCanvas::create_in_bounds(size);
+
+        if state.value.is_empty() {
+            for (x, grapheme) in self.placeholder.graphemes(true).take(size.width).enumerate() {
+                canvas.write(grapheme, x, 0);
+            }
+        } else {
+            let mask = self.mask.map(|c| c.to_string());
+            let selection = state.selection().unwrap_or_default();
+
+            for (x, grapheme) in state.graphemes().into_iter().enumerate().skip(state.offset).take(size.width) {
+                let grapheme = mask.as_deref().unwrap_or(grapheme);
+
+                match selection.contains(&x) {
+                    true => canvas.write(&(self.selection_style)(grapheme), x - state.offset, 0),
+                    false => canvas.write(grapheme, x - state.offset, 0)
+                }
+            }
+        }
+
+        if self.show_cursor {
+            // A zero width field never scrolled, the cursor can be left of the view
+            canvas.set_cursor(state.cursor.saturating_sub(state.offset), 0);
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(state: &mut TextInputState, keys: &[KeyCode]) {
+        for key in keys {
+            state.handle_key(*key, KeyModifiers::NONE);
+        }
+    }
+
+    fn rendered(state: &mut TextInputState, width: usize) -> TextCanvas {
+        TextInput::new(|state: &mut TextInputState| state).render(&Size::new(width, 1), state)
+    }
+
+    #[test]
+    fn word_motion_skips_the_whitespace_in_between() {
+        let mut state = TextInputState::with_value("one  two three");
+
+        state.handle_key(KeyCode::Left, KeyModifiers::CONTROL);
+        assert_eq!(state.cursor(), 9);
+
+        state.handle_key(KeyCode::Left, KeyModifiers::ALT);
+        assert_eq!(state.cursor(), 5);
+
+        state.handle_key(KeyCode::Left, KeyModifiers::CONTROL);
+        state.handle_key(KeyCode::Right, KeyModifiers::CONTROL);
+        assert_eq!(state.cursor(), 3);
+    }
+
+    #[test]
+    fn deleting_a_word_also_takes_the_whitespace_before_the_cursor() {
+        let mut state = TextInputState::with_value("one two  ");
+
+        state.handle_key(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(state.value(), "one ");
+
+        state.handle_key(KeyCode::Backspace, KeyModifiers::ALT);
+        assert_eq!(state.value(), "");
+    }
+
+    #[test]
+    fn edits_happen_at_the_cursor() {
+        let mut state = TextInputState::with_value("héllo");
+
+        typed(&mut state, &[KeyCode::Home, KeyCode::Right, KeyCode::Delete, KeyCode::Char('e'), KeyCode::End, KeyCode::Backspace]);
+        assert_eq!(state.value(), "hell");
+
+        state.handle_key(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        state.handle_key(KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(state.value(), "");
+    }
+
+    #[test]
+    fn pasted_newlines_are_dropped() {
+        let mut state = TextInputState::new();
+        state.handle_event::<()>(&AnimationEvent::Paste("a\r\nb".to_string()));
+
+        assert_eq!(state.value(), "ab");
+        assert_eq!(state.cursor(), 2);
+    }
+
+    #[test]
+    fn the_view_scrolls_to_keep_the_cursor_in_it() {
+        let mut state = TextInputState::with_value("abcdefgh");
+
+        let canvas = rendered(&mut state, 4);
+        assert_eq!(canvas.to_string().trim_end(), "fgh");
+        assert_eq!(canvas.cursor(), Some((3, 0)));
+
+        state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+        assert_eq!(rendered(&mut state, 4).to_string().trim_end(), "abcd");
+        assert_eq!(state.offset(), 0);
+    }
+
+    #[test]
+    fn a_shorter_value_scrolls_back_into_view() {
+        let mut state = TextInputState::with_value("a long value");
+        rendered(&mut state, 4);
+        assert!(state.offset() > 0);
+
+        state.set_value("ab");
+        assert_eq!(state.offset(), 0);
+
+        // Without a width to scroll in, the cursor isn't drawn at all
+        state.set_value("a long value");
+        rendered(&mut state, 4);
+        state.handle_key(KeyCode::Home, KeyModifiers::NONE);
+        assert_eq!(rendered(&mut state, 0).cursor(), None);
+    }
+
+    #[test]
+    fn shift_extends_the_selection_from_where_it_started() {
+        let mut state = TextInputState::with_value("one two");
+
+        state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+        state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+        assert_eq!(state.selection(), Some(5..7));
+
+        state.handle_key(KeyCode::Left, KeyModifiers::SHIFT | KeyModifiers::CONTROL);
+        assert_eq!(state.selected_text().as_deref(), Some("two"));
+
+        state.handle_key(KeyCode::Right, KeyModifiers::SHIFT);
+        assert_eq!(state.selected_text().as_deref(), Some("wo"));
+
+        state.handle_key(KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(state.selection(), None);
+    }
+
+    #[test]
+    fn typing_and_deleting_replace_the_selection() {
+        let mut state = TextInputState::with_value("one two");
+
+        typed(&mut state, &[KeyCode::Home]);
+        state.handle_key(KeyCode::Right, KeyModifiers::SHIFT | KeyModifiers::ALT);
+        state.handle_key(KeyCode::Char('1'), KeyModifiers::NONE);
+        assert_eq!(state.value(), "1 two");
+
+        state.select_all();
+        state.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(state.value(), "");
+
+        state.set_value("abc");
+        state.handle_key(KeyCode::Home, KeyModifiers::SHIFT);
+        state.handle_key(KeyCode::Right, KeyModifiers::SHIFT);
+        state.handle_key(KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(state.value(), "a");
+    }
+
+    #[test]
+    fn the_selection_is_drawn_inverted() {
+        let mut state = TextInputState::with_value("abcd");
+        state.handle_key(KeyCode::Left, KeyModifiers::NONE);
+        state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+        state.handle_key(KeyCode::Left, KeyModifiers::SHIFT);
+
+        let canvas = rendered(&mut state, 4);
+        assert_eq!(canvas.get_at(0, 0), Some("a"));
+        assert_eq!(canvas.get_at(1, 0), Some(inverted("b").as_str()));
+        assert_eq!(canvas.get_at(2, 0), Some(inverted("c").as_str()));
+        assert_eq!(canvas.get_at(3, 0), Some("d"));
+
+        let bracketed = TextInput::new(|state: &mut TextInputState| state)
+            .selection_style(|grapheme| format!("[{grapheme}]"))
+            .render(&Size::new(4, 1), &mut state);
+        assert_eq!(bracketed.get_at(1, 0), Some("[b]"));
+    }
+}
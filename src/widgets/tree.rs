@@ -0,0 +1,410 @@
+use std::{collections::{HashMap, HashSet}, rc::Rc};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{animation::{AnimationEvent, KeyCode, KeyModifiers}, canvas::TextCanvas, layout::{geometry::{Rect, Size}, sizing::{ItemSizing, Sizing}}};
+
+use super::Widget;
+
+// Nodes are found by the index of each node on the way to them, from the roots
+pub type TreePath = Vec<usize>;
+
+#[derive(Clone, Debug, PartialEq)]
+struct TreeRow {
+    path: TreePath,
+    text: String,
+    expandable: bool
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeState<Node> {
+    expanded: HashSet<TreePath>,
+    selected: Option<TreePath>,
+    // The children of each node that's been asked for them, until they're refreshed
+    children: HashMap<TreePath, Rc<Vec<Node>>>,
+    // The first row in view
+    offset: usize,
+    // The rows it was last rendered with, for moving around and expanding
+    rows: Vec<(TreePath, bool)>,
+    height: usize
+}
+
+impl<Node> Default for TreeState<Node> {
+    fn default() -> Self {
+        TreeState {
+            expanded: HashSet::new(),
+            selected: None,
+            children: HashMap::new(),
+            offset: 0,
+            rows: vec![],
+            height: 0
+        }
+    }
+}
+
+impl<Node> TreeState<Node> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Children are only asked for once. This asks for all of them again on the next render.
+    pub fn refresh(&mut self) {
+        self.children.clear();
+    }
+
+    // Asks again for the children of `path`, and for everything below it
+    pub fn refresh_path(&mut self, path: &TreePath) {
+        self.children.retain(|cached, _| !cached.starts_with(path));
+    }
+
+    pub fn selected(&self) -> Option<&TreePath> {
+        self.selected.as_ref()
+    }
+
+    pub fn select(&mut self, path: Option<TreePath>) {
+        self.selected = path;
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn is_expanded(&self, path: &TreePath) -> bool {
+        self.expanded.contains(path)
+    }
+
+    pub fn expand(&mut self, path: TreePath) {
+        self.expanded.insert(path);
+    }
+
+    pub fn collapse(&mut self, path: &TreePath) {
+        self.expanded.remove(path);
+    }
+
+    pub fn toggle(&mut self, path: TreePath) {
+        if !self.expanded.remove(&path) {
+            self.expanded.insert(path);
+        }
+    }
+
+    pub fn handle_event<Msg>(&mut self, event: &AnimationEvent<Msg>) -> bool {
+        match event {
+            AnimationEvent::KeyEvent(code, modifiers) => self.handle_key(*code, *modifiers),
+            _ => false
+        }
+    }
+
+    // Right expands or steps into a node, Left collapses it or steps out to its parent.
+    // Returns true when the key was used.
+    pub fn handle_key(&mut self, code: KeyCode, _modifiers: KeyModifiers) -> bool {
+        if self.rows.is_empty() { return false; }
+
+        let last = self.rows.len() - 1;
+        let page = self.height.max(1);
+        let current = self.selected_row();
+
+        let row = match (code, current) {
+            (KeyCode::Up, None) => last,
+            (KeyCode::Up, Some(i)) => i.saturating_sub(1),
+            (KeyCode::Down, None) => 0,
+            (KeyCode::Down, Some(i)) => (i + 1).min(last),
+            (KeyCode::PageUp, i) => i.unwrap_or(0).saturating_sub(page),
+            (KeyCode::PageDown, i) => i.map_or(0, |i| i + page).min(last),
+            (KeyCode::Home, _) => 0,
+            (KeyCode::End, _) => last,
+            (KeyCode::Right, Some(i)) => {
+                let (path, expandable) = self.rows[i].clone();
+
+                match (expandable, self.is_expanded(&path)) {
+                    (true, false) => self.expand(path),
+                    (true, true) => self.select_row((i + 1).min(last)),
+                    _ => {}
+                }
+
+                return true;
+            }
+            (KeyCode::Left, Some(i)) => {
+                let path = self.rows[i].0.clone();
+
+                if self.is_expanded(&path) {
+                    self.collapse(&path);
+                } else if path.len() > 1 {
+                    self.selected = Some(path[..path.len() - 1].to_vec());
+                }
+
+                return true;
+            }
+            (KeyCode::Enter | KeyCode::Char(' '), Some(i)) => {
+                let (path, expandable) = self.rows[i].clone();
+                if expandable { self.toggle(path); }
+
+                return true;
+            }
+            _ => return false
+        };
+
+        self.select_row(row);
+
+        true
+    }
+
+    fn selected_row(&self) -> Option<usize> {
+        let selected = self.selected.as_ref()?;
+
+        self.rows.iter().position(|(path, _)| path == selected)
+    }
+
+    fn select_row(&mut self, row: usize) {
+        self.selected = Some(self.rows[row].0.clone());
+    }
+
+    // Keeps the selection on a visible row, falling back to the closest visible ancestor,
+    // and moves the view just enough for it to be in it
+    fn update_rows(&mut self, rows: &[TreeRow], height: usize) {
+        self.rows = rows.iter().map(|r| (r.path.clone(), r.expandable)).collect();
+        self.height = height;
+
+        while let Some(selected) = &self.selected {
+            if selected.is_empty() || self.selected_row().is_some() { break; }
+            self.selected = Some(selected[..selected.len() - 1].to_vec());
+        }
+        if self.selected.as_ref().is_some_and(|s| s.is_empty()) { self.selected = None; }
+
+        if let Some(selected) = self.selected_row() {
+            if selected < self.offset {
+                self.offset = selected;
+            } else if selected >= self.offset + height {
+                self.offset = selected + 1 - height;
+            }
+        }
+
+        self.offset = self.offset.min(rows.len().saturating_sub(height));
+    }
+}
+
+type ChildrenFn<Node> = Box<dyn Fn(&Node) -> Vec<Node>>;
+type HasChildrenFn<Node> = Box<dyn Fn(&Node) -> bool>;
+
+// Nodes of any type, with their children asked for only when they're needed, and kept
+// in the state until it's refreshed
+pub struct Tree<Ctx, Node> {
+    roots: Vec<Node>,
+    label: Box<dyn Fn(&Node) -> String>,
+    children: ChildrenFn<Node>,
+    has_children: Option<HasChildrenFn<Node>>,
+    state: fn(&mut Ctx) -> &mut TreeState<Node>,
+    highlight_symbol: String
+}
+
+impl<Ctx, Node> Tree<Ctx, Node> {
+    pub fn new(
+        roots: Vec<Node>,
+        label: impl Fn(&Node) -> String + 'static,
+        children: impl Fn(&Node) -> Vec<Node> + 'static,
+        state: fn(&mut Ctx) -> &mut TreeState<Node>
+    ) -> Self {
+        Tree {
+            roots,
+            label: Box::new(label),
+            children: Box::new(children),
+            has_children: None,
+            state,
+            highlight_symbol: "> ".to_string()
+        }
+    }
+
+    // Tells collapsed nodes apart from leaves without loading their children
+    pub fn has_children(mut self, has_children: impl Fn(&Node) -> bool + 'static) -> Self {
+        self.has_children = Some(Box::new(has_children));
+        self
+    }
+
+    // Drawn before the selected row, with every other row indented as much
+    pub fn highlight_symbol(mut self, symbol: &str) -> Self {
+        self.highlight_symbol = symbol.to_string();
+        self
+    }
+
+    fn children_of(&self, node: &Node, path: &TreePath, state: &mut TreeState<Node>) -> Rc<Vec<Node>> {
+        state.children.entry(path.clone()).or_insert_with(|| Rc::new((self.children)(node))).clone()
+    }
+
+    fn is_expandable(&self, node: &Node, path: &TreePath, state: &mut TreeState<Node>) -> bool {
+        match &self.has_children {
+            Some(has_children) => has_children(node),
+            None => !self.children_of(node, path, state).is_empty()
+        }
+    }
+
+    // The visible rows, depth first. `guides` are the lines drawn for the ancestors of `nodes`.
+    fn flatten(&self, nodes: &[Node], path: &mut TreePath, guides: &str, state: &mut TreeState<Node>, rows: &mut Vec<TreeRow>) {
+        for (i, node) in nodes.iter().enumerate() {
+            path.push(i);
+
+            let is_root = path.len() == 1;
+            let is_last = i + 1 == nodes.len();
+
+            let expandable = self.is_expandable(node, path, state);
+            let expanded = expandable && state.is_expanded(path);
+
+            let connector = match (is_root, is_last) {
+                (true, _) => "",
+                (false, true) => "└─ ",
+                (false, false) => "├─ "
+            };
+            let marker = match (expandable, expanded) {
+                (false, _) => "",
+                (true, false) => "▸ ",
+                (true, true) => "▾ "
+            };
+
+            rows.push(TreeRow {
+                path: path.clone(),
+                text: format!("{guides}{connector}{marker}{}", (self.label)(node)),
+                expandable
+            });
+
+            if expanded {
+                let child_guides = match (is_root, is_last) {
+                    (true, _) => String::new(),
+                    (false, true) => format!("{guides}   "),
+                    (false, false) => format!("{guides}│  ")
+                };
+
+                let children = self.children_of(node, path, state);
+                self.flatten(&children, path, &child_guides, state, rows);
+            }
+
+            path.pop();
+        }
+    }
+}
+
+impl<Ctx, Node> Widget<Ctx> for Tree<Ctx, Node> {
+    fn sizing(&self, _bounds: &Rect, _context: &mut Ctx) -> ItemSizing {
+        ItemSizing::new(Sizing::Greedy(1), Sizing::Greedy(1))
+    }
+
+    fn render(&self, size: &Size, context: &mut Ctx) -> TextCanvas {
+        let mut canvas = TextCanvas::create_in_bounds(size);
+
+        let state = (self.state)(context);
+
+        let mut rows = vec![];
+        self.flatten(&self.roots, &mut vec![], "", state, &mut rows);
+        state.update_rows(&rows, size.height);
+
+        let indent = " ".repeat(self.highlight_symbol.graphemes(true).count());
+
+        for (y, row) in rows.iter().skip(state.offset).take(size.height).enumerate() {
+            let prefix = if state.selected.as_ref() == Some(&row.path) { &self.highlight_symbol } else { &indent };
+            let text = format!("{prefix}{}", row.text);
+
+            for (x, grapheme) in text.graphemes(true).take(size.width).enumerate() {
+                canvas.write(grapheme, x, y);
+            }
+        }
+
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use super::*;
+
+    type Files = Rc<RefCell<HashMap<&'static str, Vec<&'static str>>>>;
+
+    fn files() -> Files {
+        Rc::new(RefCell::new(HashMap::from([
+            ("src", vec!["main.rs", "widgets"]),
+            ("widgets", vec!["tree.rs"])
+        ])))
+    }
+
+    // A tree over `files`, counting how many times children are asked for
+    fn tree(files: &Files, loads: &Rc<Cell<usize>>) -> Tree<TreeState<&'static str>, &'static str> {
+        let (files, loads) = (files.clone(), loads.clone());
+
+        Tree::new(
+            vec!["src", "README.md"],
+            |node: &&str| node.to_string(),
+            move |node: &&str| {
+                loads.set(loads.get() + 1);
+                files.borrow().get(node).cloned().unwrap_or_default()
+            },
+            |s: &mut TreeState<&'static str>| s
+        )
+    }
+
+    fn rendered(tree: &Tree<TreeState<&'static str>, &'static str>, state: &mut TreeState<&'static str>, height: usize) -> Vec<String> {
+        tree.render(&Size::new(20, height), state).to_string().lines()
+            .map(|line| line.trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn expanded_nodes_show_their_children() {
+        let loads = Rc::new(Cell::new(0));
+        let tree = tree(&files(), &loads);
+        let mut state = TreeState::new();
+        state.expand(vec![0]);
+        state.expand(vec![0, 1]);
+        state.select(Some(vec![0, 1, 0]));
+
+        assert_eq!(rendered(&tree, &mut state, 5), vec![
+            "  ▾ src",
+            "  ├─ main.rs",
+            "  └─ ▾ widgets",
+            ">    └─ tree.rs",
+            "  README.md"
+        ]);
+    }
+
+    #[test]
+    fn children_are_cached_until_refreshed() {
+        let (files, loads) = (files(), Rc::new(Cell::new(0)));
+        let tree = tree(&files, &loads);
+        let mut state = TreeState::new();
+        state.expand(vec![0]);
+
+        rendered(&tree, &mut state, 5);
+        let first = loads.get();
+        rendered(&tree, &mut state, 5);
+        assert_eq!(loads.get(), first);
+
+        files.borrow_mut().get_mut("src").unwrap().push("lib.rs");
+        assert_eq!(rendered(&tree, &mut state, 5)[3], "  README.md");
+
+        state.refresh_path(&vec![0]);
+        assert_eq!(rendered(&tree, &mut state, 5)[3], "  └─ lib.rs");
+        assert!(loads.get() > first);
+
+        let refreshed = loads.get();
+        state.refresh();
+        rendered(&tree, &mut state, 5);
+        assert!(loads.get() > refreshed);
+    }
+
+    #[test]
+    fn right_expands_and_left_steps_out() {
+        let tree = tree(&files(), &Rc::new(Cell::new(0)));
+        let mut state = TreeState::new();
+
+        rendered(&tree, &mut state, 5);
+        assert!(state.handle_key(KeyCode::Down, KeyModifiers::NONE));
+        assert!(state.handle_key(KeyCode::Right, KeyModifiers::NONE));
+        assert!(state.is_expanded(&vec![0]));
+
+        rendered(&tree, &mut state, 5);
+        state.handle_key(KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(state.selected(), Some(&vec![0, 0]));
+
+        state.handle_key(KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(state.selected(), Some(&vec![0]));
+        state.handle_key(KeyCode::Left, KeyModifiers::NONE);
+        assert!(!state.is_expanded(&vec![0]));
+    }
+}